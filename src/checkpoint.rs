@@ -0,0 +1,35 @@
+use anyhow::Result;
+use std::path::Path;
+
+/// Durable, per-slot record of the last LSN every output target has
+/// accepted, backed by an embedded `sled` database.
+///
+/// This closes the gap left by in-memory flush tracking: if the process
+/// crashes between a target durably writing a change and that LSN being
+/// acknowledged to Postgres, the checkpoint lets the next run resume from
+/// exactly the right place instead of replaying or dropping events.
+pub struct CheckpointStore {
+    db: sled::Db,
+}
+
+impl CheckpointStore {
+    pub fn open(dir: &Path) -> Result<Self> {
+        let db = sled::open(dir)?;
+        Ok(Self { db })
+    }
+
+    /// Last checkpointed LSN for `slot_name`, if one has been recorded.
+    pub fn get(&self, slot_name: &str) -> Result<Option<String>> {
+        match self.db.get(slot_name.as_bytes())? {
+            Some(value) => Ok(Some(String::from_utf8(value.to_vec())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Persist `lsn` as the last durably-accepted position for `slot_name`.
+    pub fn set(&self, slot_name: &str, lsn: &str) -> Result<()> {
+        self.db.insert(slot_name.as_bytes(), lsn.as_bytes())?;
+        self.db.flush()?;
+        Ok(())
+    }
+}
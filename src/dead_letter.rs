@@ -0,0 +1,65 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::decoder::Change;
+
+/// Records changes (or raw bytes) that couldn't be delivered after
+/// exhausting retries, one JSON object per line, mirroring pict-rs's
+/// `InvalidJob` error which captures the raw payload instead of discarding it.
+pub struct DeadLetterSink {
+    file: Mutex<File>,
+}
+
+#[derive(Serialize)]
+struct WriteFailureRecord<'a> {
+    lsn: Option<&'a str>,
+    target: &'a str,
+    change: &'a Change,
+    error: String,
+}
+
+#[derive(Serialize)]
+struct DecodeFailureRecord<'a> {
+    raw_base64: String,
+    error: &'a str,
+}
+
+impl DeadLetterSink {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file: Mutex::new(file) })
+    }
+
+    /// Record a change that a specific output target failed to write after
+    /// exhausting its retries.
+    pub fn record_write_failure(&self, target: &str, lsn: Option<&str>, change: &Change, error: &anyhow::Error) -> Result<()> {
+        let record = WriteFailureRecord { lsn, target, change, error: error.to_string() };
+        self.write_line(&record)
+    }
+
+    /// Record a pgoutput message that failed to decode, so the replication
+    /// stream can keep moving instead of aborting on a single malformed
+    /// buffer.
+    pub fn record_decode_failure(&self, raw: &[u8], error: &anyhow::Error) -> Result<()> {
+        use base64::Engine;
+        let record = DecodeFailureRecord {
+            raw_base64: base64::engine::general_purpose::STANDARD.encode(raw),
+            error: &error.to_string(),
+        };
+        self.write_line(&record)
+    }
+
+    fn write_line<T: Serialize>(&self, record: &T) -> Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+
+        let mut file = self.file.lock().unwrap();
+        file.write_all(&line)?;
+        file.flush()?;
+        Ok(())
+    }
+}
@@ -1,8 +1,11 @@
 use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::sync::Mutex;
-use once_cell::sync::Lazy;
+
+use crate::lsn::Lsn;
+use crate::pg_type::{self, PgType};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Change {
@@ -19,20 +22,20 @@ pub enum Change {
         relation_id: u32,
         schema: String,
         table: String,
-        new_tuple: HashMap<String, Option<String>>,
+        new_tuple: IndexMap<String, Option<String>>,
     },
     Update {
         relation_id: u32,
         schema: String,
         table: String,
-        old_tuple: Option<HashMap<String, Option<String>>>,
-        new_tuple: HashMap<String, Option<String>>,
+        old_tuple: Option<IndexMap<String, Option<String>>>,
+        new_tuple: IndexMap<String, Option<String>>,
     },
     Delete {
         relation_id: u32,
         schema: String,
         table: String,
-        old_tuple: HashMap<String, Option<String>>,
+        old_tuple: IndexMap<String, Option<String>>,
     },
     Relation {
         relation_id: u32,
@@ -40,6 +43,128 @@ pub enum Change {
         table: String,
         columns: Vec<ColumnInfo>,
     },
+    /// Marks the start of a streamed (in-progress) transaction that Postgres
+    /// is spilling to the subscriber before commit. All `I`/`U`/`D` messages
+    /// until the matching `StreamStop` are prefixed with `xid` and decoded as
+    /// `StreamInsert`/`StreamUpdate`/`StreamDelete`.
+    StreamStart {
+        xid: u32,
+        first_segment: bool,
+    },
+    /// Marks the end of the current chunk of a streamed transaction; more
+    /// chunks for the same `xid` may follow in a later `StreamStart`.
+    StreamStop,
+    /// Commits a streamed transaction previously opened with `StreamStart`.
+    StreamCommit {
+        xid: u32,
+        commit_lsn: String,
+        end_lsn: String,
+        timestamp: i64,
+    },
+    /// Aborts (rolls back) a streamed transaction, or a subtransaction of one.
+    StreamAbort {
+        xid: u32,
+        subxid: u32,
+    },
+    /// One or more tables were truncated together, resolved to
+    /// schema/table pairs via the relation cache.
+    Truncate {
+        relations: Vec<(String, String)>,
+        cascade: bool,
+        restart_identity: bool,
+    },
+    /// Replication origin of the just-committed transaction, sent when the
+    /// origin is tracked (e.g. bidirectional replication).
+    Origin {
+        commit_lsn: String,
+        name: String,
+    },
+    /// Describes a column's custom (non-built-in) type, analogous to
+    /// `Relation` but for the type system rather than a table.
+    Type {
+        type_id: u32,
+        namespace: String,
+        name: String,
+    },
+    /// A generic logical decoding message emitted via `pg_logical_emit_message`.
+    LogicalMessage {
+        transactional: bool,
+        lsn: String,
+        prefix: String,
+        content: Vec<u8>,
+    },
+    /// An INSERT that arrived inside a streamed (in-progress) transaction,
+    /// i.e. after `StreamStart` and before the matching `StreamStop`/`StreamCommit`.
+    StreamInsert {
+        xid: u32,
+        relation_id: u32,
+        schema: String,
+        table: String,
+        new_tuple: IndexMap<String, Option<String>>,
+    },
+    /// An UPDATE that arrived inside a streamed (in-progress) transaction.
+    StreamUpdate {
+        xid: u32,
+        relation_id: u32,
+        schema: String,
+        table: String,
+        old_tuple: Option<IndexMap<String, Option<String>>>,
+        new_tuple: IndexMap<String, Option<String>>,
+    },
+    /// A DELETE that arrived inside a streamed (in-progress) transaction.
+    StreamDelete {
+        xid: u32,
+        relation_id: u32,
+        schema: String,
+        table: String,
+        old_tuple: IndexMap<String, Option<String>>,
+    },
+    /// Opens a two-phase-commit transaction (`PREPARE TRANSACTION`) on a
+    /// publication created with `two_phase = true`.
+    BeginPrepare {
+        prepare_lsn: String,
+        end_lsn: String,
+        prepare_timestamp: i64,
+        xid: u32,
+        gid: String,
+    },
+    /// The transaction has been prepared and is now waiting for
+    /// `COMMIT PREPARED`/`ROLLBACK PREPARED`.
+    Prepare {
+        prepare_lsn: String,
+        end_lsn: String,
+        prepare_timestamp: i64,
+        xid: u32,
+        gid: String,
+    },
+    /// `COMMIT PREPARED <gid>` was executed, finalizing the two-phase
+    /// transaction.
+    CommitPrepared {
+        commit_lsn: String,
+        end_lsn: String,
+        commit_timestamp: i64,
+        xid: u32,
+        gid: String,
+    },
+    /// `ROLLBACK PREPARED <gid>` was executed, discarding the two-phase
+    /// transaction.
+    RollbackPrepared {
+        prepare_end_lsn: String,
+        rollback_end_lsn: String,
+        prepare_timestamp: i64,
+        rollback_timestamp: i64,
+        xid: u32,
+        gid: String,
+    },
+    /// Like `Prepare`, but for a two-phase transaction that was also
+    /// streamed (spilled) before being prepared.
+    StreamPrepare {
+        prepare_lsn: String,
+        end_lsn: String,
+        prepare_timestamp: i64,
+        xid: u32,
+        gid: String,
+    },
 }
 
 impl Change {
@@ -48,9 +173,165 @@ impl Change {
         match self {
             Change::Begin { lsn, .. } => Some(lsn),
             Change::Commit { lsn, .. } => Some(lsn),
+            Change::StreamCommit { commit_lsn, .. } => Some(commit_lsn),
+            Change::Origin { commit_lsn, .. } => Some(commit_lsn),
+            Change::LogicalMessage { lsn, .. } => Some(lsn),
+            Change::BeginPrepare { prepare_lsn, .. } => Some(prepare_lsn),
+            Change::Prepare { prepare_lsn, .. } => Some(prepare_lsn),
+            Change::CommitPrepared { commit_lsn, .. } => Some(commit_lsn),
+            Change::RollbackPrepared { rollback_end_lsn, .. } => Some(rollback_end_lsn),
+            Change::StreamPrepare { prepare_lsn, .. } => Some(prepare_lsn),
             _ => None,
         }
     }
+
+    /// Typed form of `get_lsn`, parsed on demand from the same string this
+    /// `Change` already carries. `get_lsn` stays string-based so existing
+    /// consumers of it aren't affected.
+    pub fn lsn_typed(&self) -> Option<Lsn> {
+        self.get_lsn().and_then(|s| s.parse().ok())
+    }
+
+    /// Short, lowercase operation name used as a metrics label.
+    pub fn operation_name(&self) -> &'static str {
+        match self {
+            Change::Begin { .. } => "begin",
+            Change::Commit { .. } => "commit",
+            Change::Relation { .. } => "relation",
+            Change::Insert { .. } => "insert",
+            Change::Update { .. } => "update",
+            Change::Delete { .. } => "delete",
+            Change::StreamStart { .. } => "stream_start",
+            Change::StreamStop => "stream_stop",
+            Change::StreamCommit { .. } => "stream_commit",
+            Change::StreamAbort { .. } => "stream_abort",
+            Change::Truncate { .. } => "truncate",
+            Change::Origin { .. } => "origin",
+            Change::Type { .. } => "type",
+            Change::LogicalMessage { .. } => "message",
+            Change::StreamInsert { .. } => "stream_insert",
+            Change::StreamUpdate { .. } => "stream_update",
+            Change::StreamDelete { .. } => "stream_delete",
+            Change::BeginPrepare { .. } => "begin_prepare",
+            Change::Prepare { .. } => "prepare",
+            Change::CommitPrepared { .. } => "commit_prepared",
+            Change::RollbackPrepared { .. } => "rollback_prepared",
+            Change::StreamPrepare { .. } => "stream_prepare",
+        }
+    }
+
+    /// Serialize like `serde_json::to_value`, but with `old_tuple`/`new_tuple`
+    /// columns typed via `typed_tuple` instead of left as quoted strings.
+    /// Opt-in: plain `serde_json::to_value`/`to_vec` on a `Change` keeps
+    /// working exactly as before for callers that want the raw string form.
+    ///
+    /// `decoder` must be the same `Decoder` that decoded this change, so its
+    /// relation/custom-type cache actually has this change's columns -
+    /// see `typed_tuple`.
+    pub fn to_typed_json(&self, decoder: &Decoder) -> Result<serde_json::Value> {
+        let mut value = serde_json::to_value(self)?;
+
+        let Some((_, variant)) = value.as_object_mut().and_then(|obj| obj.iter_mut().next()) else {
+            return Ok(value);
+        };
+        let Some(fields) = variant.as_object_mut() else {
+            return Ok(value);
+        };
+        let Some(relation_id) = fields.get("relation_id").and_then(|v| v.as_u64()) else {
+            return Ok(value);
+        };
+
+        for tuple_field in ["old_tuple", "new_tuple"] {
+            let Some(raw) = fields.get(tuple_field).and_then(|v| v.as_object()) else {
+                continue;
+            };
+            let raw_tuple: IndexMap<String, Option<String>> =
+                raw.iter().map(|(k, v)| (k.clone(), v.as_str().map(str::to_string))).collect();
+            let typed: serde_json::Map<String, serde_json::Value> = typed_tuple(decoder, relation_id as u32, &raw_tuple)
+                .into_iter()
+                .map(|(k, v)| (k, v.to_json()))
+                .collect();
+            fields.insert(tuple_field.to_string(), serde_json::Value::Object(typed));
+        }
+
+        Ok(value)
+    }
+
+    /// `(schema, table, operation, xid)` used by `SubjectBuilder` to fill in
+    /// a subject template. Table-scoped changes use their real schema/table;
+    /// changes with no table (transactions, stream control, type-system
+    /// metadata) use the `"transactions"`/`"system"` placeholders this crate
+    /// has always routed them under, with the event's own name standing in
+    /// for `table` and `"event"` for `operation`. `xid` is `0` for changes
+    /// that don't carry one.
+    fn subject_parts(&self) -> (String, String, String, u32) {
+        match self {
+            Change::Begin { xid, .. } => ("transactions".to_string(), "begin".to_string(), "event".to_string(), *xid),
+            Change::Commit { .. } => ("transactions".to_string(), "commit".to_string(), "event".to_string(), 0),
+            Change::Relation { schema, table, .. } => (schema.clone(), table.clone(), "relation".to_string(), 0),
+            Change::Insert { schema, table, .. } => (schema.clone(), table.clone(), "insert".to_string(), 0),
+            Change::Update { schema, table, .. } => (schema.clone(), table.clone(), "update".to_string(), 0),
+            Change::Delete { schema, table, .. } => (schema.clone(), table.clone(), "delete".to_string(), 0),
+            Change::StreamInsert { schema, table, xid, .. } => (schema.clone(), table.clone(), "insert".to_string(), *xid),
+            Change::StreamUpdate { schema, table, xid, .. } => (schema.clone(), table.clone(), "update".to_string(), *xid),
+            Change::StreamDelete { schema, table, xid, .. } => (schema.clone(), table.clone(), "delete".to_string(), *xid),
+            Change::StreamStart { xid, .. } => {
+                ("transactions".to_string(), "stream_start".to_string(), "event".to_string(), *xid)
+            }
+            Change::StreamStop => ("transactions".to_string(), "stream_stop".to_string(), "event".to_string(), 0),
+            Change::StreamCommit { xid, .. } => {
+                ("transactions".to_string(), "stream_commit".to_string(), "event".to_string(), *xid)
+            }
+            Change::StreamAbort { xid, .. } => {
+                ("transactions".to_string(), "stream_abort".to_string(), "event".to_string(), *xid)
+            }
+            Change::Truncate { relations, .. } => match relations.first() {
+                Some((schema, table)) => (schema.clone(), table.clone(), "truncate".to_string(), 0),
+                None => ("system".to_string(), "truncate".to_string(), "event".to_string(), 0),
+            },
+            Change::Origin { .. } => ("transactions".to_string(), "origin".to_string(), "event".to_string(), 0),
+            Change::Type { .. } => ("system".to_string(), "type".to_string(), "event".to_string(), 0),
+            Change::LogicalMessage { .. } => ("system".to_string(), "message".to_string(), "event".to_string(), 0),
+            Change::BeginPrepare { xid, .. } => {
+                ("transactions".to_string(), "begin_prepare".to_string(), "event".to_string(), *xid)
+            }
+            Change::Prepare { xid, .. } => ("transactions".to_string(), "prepare".to_string(), "event".to_string(), *xid),
+            Change::CommitPrepared { xid, .. } => {
+                ("transactions".to_string(), "commit_prepared".to_string(), "event".to_string(), *xid)
+            }
+            Change::RollbackPrepared { xid, .. } => {
+                ("transactions".to_string(), "rollback_prepared".to_string(), "event".to_string(), *xid)
+            }
+            Change::StreamPrepare { xid, .. } => {
+                ("transactions".to_string(), "stream_prepare".to_string(), "event".to_string(), *xid)
+            }
+        }
+    }
+
+    /// Build this change's NATS subject from a configured `SubjectBuilder`.
+    /// Always `Some` today (every variant has a subject), but returns
+    /// `Option` so a future variant genuinely without a sensible subject can
+    /// opt out without a breaking signature change.
+    pub fn subject(&self, builder: &crate::subject::SubjectBuilder) -> Option<String> {
+        Some(builder.build(self))
+    }
+
+    /// Every subject this change should be routed under. A multi-table
+    /// `TRUNCATE a, b, c` routes under one subject per table - `subject()`
+    /// alone would collapse it onto just the first table's subject, silently
+    /// losing routing visibility for the rest - every other variant yields
+    /// the same single subject `subject()` does.
+    pub fn subjects(&self, builder: &crate::subject::SubjectBuilder) -> Vec<String> {
+        if let Change::Truncate { relations, .. } = self {
+            if !relations.is_empty() {
+                return relations
+                    .iter()
+                    .map(|(schema, table)| builder.build_parts(schema, table, "truncate", 0))
+                    .collect();
+            }
+        }
+        self.subject(builder).into_iter().collect()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,270 +341,856 @@ pub struct ColumnInfo {
     pub flags: u8,
 }
 
-// Thread-safe relation cache
-static RELATION_CACHE: Lazy<Mutex<HashMap<u32, (String, String, Vec<ColumnInfo>)>>> = 
-    Lazy::new(|| Mutex::new(HashMap::new()));
-
-/// Get column metadata for a relation from the cache
-pub fn get_relation_columns(relation_id: u32) -> Option<Vec<ColumnInfo>> {
-    let cache = RELATION_CACHE.lock().unwrap();
-    cache.get(&relation_id).map(|(_, _, cols)| cols.clone())
+/// A column value interpreted according to its PostgreSQL type OID, as an
+/// opt-in alternative to the raw `Option<String>` tuples `decode_pgoutput_message`
+/// produces. See `typed_tuple`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int2(i16),
+    Int4(i32),
+    Int8(i64),
+    Float4(f32),
+    Float8(f64),
+    Numeric(String),
+    Text(String),
+    Bytea(Vec<u8>),
+    Uuid(String),
+    Json(String),
+    Date(String),
+    Timestamp(String),
+    Timestamptz(String),
+    /// A non-built-in (user-defined/enum) type learned from a `Type`
+    /// message, resolved via `Decoder::get_custom_type` when `pg_type::lookup`
+    /// misses. Enum values (the common case) are sent as their text label in
+    /// both the text and binary tuple formats, so the raw text is preserved
+    /// as-is; `type_name` is `namespace.name` for a consumer that wants to
+    /// tell custom types apart from plain strings.
+    Custom { type_name: String, value: String },
+    /// The type_id had no entry in the OID registry, or the text payload
+    /// didn't parse as its mapped type; the raw text is preserved so no
+    /// information is lost.
+    Unknown(String),
 }
 
-pub fn decode_pgoutput_message(data: &[u8]) -> Result<Option<Change>> {
-    if data.is_empty() {
-        return Ok(None);
-    }
-
-    let msg_type = data[0] as char;
-    let rest = &data[1..];
-
-    match msg_type {
-        'B' => decode_begin(rest),
-        'C' => decode_commit(rest),
-        'R' => decode_relation(rest),
-        'I' => decode_insert(rest),
-        'U' => decode_update(rest),
-        'D' => decode_delete(rest),
-        'O' | 'T' | 'Y' => {
-            // Origin, Type, Truncate - not implemented yet
-            Ok(None)
-        }
-        _ => {
-            eprintln!("Unknown message type: {}", msg_type);
-            Ok(None)
+impl Value {
+    /// Render as a `serde_json::Value` with the JSON type that actually
+    /// matches the column (number, bool, null, ...) instead of the quoted
+    /// string every column gets when a tuple is serialized as-is. `Json`
+    /// columns are parsed so nested objects/arrays come through as JSON,
+    /// not as a JSON string containing JSON; anything that fails to parse
+    /// falls back to a JSON string so no information is lost. `Bytea`
+    /// columns are base64-encoded (not the `\x`-hex Postgres text format)
+    /// so binary payloads round-trip losslessly through JSON without a
+    /// consumer having to know Postgres's own bytea text encoding.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Null => serde_json::Value::Null,
+            Value::Bool(b) => serde_json::Value::Bool(*b),
+            Value::Int2(n) => serde_json::Value::Number((*n).into()),
+            Value::Int4(n) => serde_json::Value::Number((*n).into()),
+            Value::Int8(n) => serde_json::Value::Number((*n).into()),
+            Value::Float4(n) => serde_json::Number::from_f64(*n as f64)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            Value::Float8(n) => serde_json::Number::from_f64(*n)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            Value::Numeric(text) => text
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(serde_json::Value::Number)
+                .unwrap_or_else(|| serde_json::Value::String(text.clone())),
+            Value::Json(text) => serde_json::from_str(text).unwrap_or_else(|_| serde_json::Value::String(text.clone())),
+            Value::Text(text) | Value::Uuid(text) | Value::Date(text) | Value::Timestamp(text) | Value::Timestamptz(text) | Value::Unknown(text) => {
+                serde_json::Value::String(text.clone())
+            }
+            Value::Custom { value, .. } => serde_json::Value::String(value.clone()),
+            Value::Bytea(bytes) => {
+                use base64::{engine::general_purpose::STANDARD, Engine as _};
+                serde_json::Value::String(STANDARD.encode(bytes))
+            }
         }
     }
 }
 
-fn decode_begin(data: &[u8]) -> Result<Option<Change>> {
-    if data.len() < 20 {
-        return Err(anyhow!("Invalid BEGIN message length"));
-    }
+/// Reinterpret a decoded tuple's raw text values as typed `Value`s, using
+/// the `type_id` of each column cached from the relation's RELATION message.
+/// Opt-in: `decode_pgoutput_message` keeps returning `Option<String>` tuples
+/// unchanged, so existing string-based consumers aren't affected. Preserves
+/// `tuple`'s column order, the same way `tuple` itself preserves the order
+/// declared by the relation's `Change::Relation` event.
+///
+/// Takes the `Decoder` that actually decoded this tuple's relation, rather
+/// than reading a thread-local default - a `ReplicationStream` owns its own
+/// `Decoder` (so two streams in the same process never share state) and
+/// never touches the thread-local one, so resolving columns through it here
+/// would always miss.
+pub fn typed_tuple(decoder: &Decoder, relation_id: u32, tuple: &IndexMap<String, Option<String>>) -> IndexMap<String, Value> {
+    let columns = decoder.get_relation_columns(relation_id);
 
-    let lsn = u64::from_be_bytes(data[0..8].try_into()?);
-    let timestamp = i64::from_be_bytes(data[8..16].try_into()?);
-    let xid = u32::from_be_bytes(data[16..20].try_into()?);
+    tuple
+        .iter()
+        .map(|(name, raw)| {
+            let type_id = columns.as_ref().and_then(|cols| cols.iter().find(|c| &c.name == name)).map(|c| c.type_id);
+            let value = match raw {
+                None => Value::Null,
+                Some(text) => type_id
+                    .and_then(pg_type::lookup)
+                    .map(|pg_type| parse_typed_value(pg_type, text))
+                    .or_else(|| {
+                        type_id.and_then(|id| decoder.get_custom_type(id)).map(|(namespace, name)| Value::Custom {
+                            type_name: format!("{}.{}", namespace, name),
+                            value: text.clone(),
+                        })
+                    })
+                    .unwrap_or_else(|| Value::Unknown(text.clone())),
+            };
+            (name.clone(), value)
+        })
+        .collect()
+}
 
-    Ok(Some(Change::Begin {
-        lsn: format!("{:X}/{:X}", lsn >> 32, lsn & 0xFFFFFFFF),
-        timestamp,
-        xid,
-    }))
+fn parse_typed_value(pg_type: PgType, text: &str) -> Value {
+    match pg_type {
+        PgType::Bool => Value::Bool(text == "t" || text == "true"),
+        PgType::Int2 => text.parse().map(Value::Int2).unwrap_or_else(|_| Value::Unknown(text.to_string())),
+        PgType::Int4 => text.parse().map(Value::Int4).unwrap_or_else(|_| Value::Unknown(text.to_string())),
+        PgType::Int8 => text.parse().map(Value::Int8).unwrap_or_else(|_| Value::Unknown(text.to_string())),
+        PgType::Float4 => text.parse().map(Value::Float4).unwrap_or_else(|_| Value::Unknown(text.to_string())),
+        PgType::Float8 => text.parse().map(Value::Float8).unwrap_or_else(|_| Value::Unknown(text.to_string())),
+        PgType::Numeric => Value::Numeric(text.to_string()),
+        PgType::Text => Value::Text(text.to_string()),
+        PgType::Bytea => parse_bytea(text),
+        PgType::Uuid => Value::Uuid(text.to_string()),
+        PgType::Json => Value::Json(text.to_string()),
+        PgType::Date => Value::Date(text.to_string()),
+        PgType::Timestamp => Value::Timestamp(text.to_string()),
+        PgType::Timestamptz => Value::Timestamptz(text.to_string()),
+    }
 }
 
-fn decode_commit(data: &[u8]) -> Result<Option<Change>> {
-    if data.len() < 17 {
-        return Err(anyhow!("Invalid COMMIT message length"));
+/// Parse Postgres's text-format bytea (`\xDEADBEEF`) into raw bytes.
+fn parse_bytea(text: &str) -> Value {
+    match text.strip_prefix("\\x") {
+        Some(hex) => {
+            let bytes = (0..hex.len())
+                .step_by(2)
+                .filter_map(|i| hex.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+                .collect();
+            Value::Bytea(bytes)
+        }
+        None => Value::Unknown(text.to_string()),
     }
+}
 
-    let _flags = data[0];
-    let lsn = u64::from_be_bytes(data[1..9].try_into()?);
-    let _end_lsn = u64::from_be_bytes(data[9..17].try_into()?);
-    let timestamp = i64::from_be_bytes(data[17..25].try_into()?);
+/// Owns the per-stream decoding state: the relation metadata cache
+/// populated by `Relation` messages and read back by `Insert`/`Update`/
+/// `Delete`, whether a streamed transaction is currently open, and the
+/// negotiated `proto_version`. Each `ReplicationStream` (one per slot)
+/// should own its own `Decoder` so two streams in the same process never
+/// clobber each other's relation cache.
+pub struct Decoder {
+    relation_cache: HashMap<u32, (String, String, Vec<ColumnInfo>)>,
+    /// Whether we're currently between a `StreamStart` and its matching
+    /// `StreamStop`/`StreamCommit`. While true, `I`/`U`/`D`/`R` messages are
+    /// prefixed with a 4-byte xid and decoded as `StreamInsert`/
+    /// `StreamUpdate`/`StreamDelete` instead of the regular variants.
+    streaming: bool,
+    /// The `proto_version` negotiated with `START_REPLICATION`. Streamed
+    /// transactions (and the xid prefix they add to row/relation messages)
+    /// only exist under protocol version 2+.
+    protocol_version: u8,
+    /// Non-built-in (user-defined/enum) type names learned from `Type`
+    /// messages, keyed by type OID. `pg_type::lookup` only covers the
+    /// built-in OIDs known at compile time; this is the runtime-learned
+    /// complement so typed decoding can at least name a column's type
+    /// even when it can't yet parse its value.
+    custom_types: HashMap<u32, (String, String)>,
+}
 
-    Ok(Some(Change::Commit {
-        lsn: format!("{:X}/{:X}", lsn >> 32, lsn & 0xFFFFFFFF),
-        timestamp,
-    }))
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-fn decode_relation(data: &[u8]) -> Result<Option<Change>> {
-    let mut pos = 0;
+impl Decoder {
+    pub fn new() -> Self {
+        Self {
+            relation_cache: HashMap::new(),
+            streaming: false,
+            protocol_version: 1,
+            custom_types: HashMap::new(),
+        }
+    }
 
-    let relation_id = u32::from_be_bytes(data[pos..pos + 4].try_into()?);
-    pos += 4;
+    /// Record the `proto_version` negotiated with `START_REPLICATION`.
+    pub fn set_protocol_version(&mut self, version: u8) {
+        self.protocol_version = version;
+    }
 
-    let schema = read_string(data, &mut pos)?;
-    let table = read_string(data, &mut pos)?;
+    /// Get column metadata for a relation from this decoder's cache.
+    pub fn get_relation_columns(&self, relation_id: u32) -> Option<Vec<ColumnInfo>> {
+        self.relation_cache.get(&relation_id).map(|(_, _, cols)| cols.clone())
+    }
 
-    let _replica_identity = data[pos];
-    pos += 1;
+    /// Look up a custom (non-built-in) type's `namespace.name` learned from
+    /// an earlier `Type` message, keyed by its OID.
+    pub fn get_custom_type(&self, type_id: u32) -> Option<(String, String)> {
+        self.custom_types.get(&type_id).cloned()
+    }
 
-    let column_count = u16::from_be_bytes(data[pos..pos + 2].try_into()?) as usize;
-    pos += 2;
+    fn streaming_prefix_active(&self) -> bool {
+        self.streaming && self.protocol_version >= 2
+    }
+
+    pub fn decode_message(&mut self, data: &[u8]) -> Result<Option<Change>> {
+        if data.is_empty() {
+            return Ok(None);
+        }
 
-    let mut columns = Vec::new();
-    for _ in 0..column_count {
-        let _flags = data[pos];
+        let msg_type = data[0] as char;
+        let rest = &data[1..];
+
+        match msg_type {
+            'B' => decode_begin(rest),
+            'C' => decode_commit(rest),
+            'R' => {
+                if self.streaming_prefix_active() {
+                    if rest.len() < 4 {
+                        return Err(anyhow!("Invalid streamed RELATION message length"));
+                    }
+                    self.decode_relation(&rest[4..])
+                } else {
+                    self.decode_relation(rest)
+                }
+            }
+            'I' => {
+                if self.streaming_prefix_active() {
+                    self.decode_stream_insert(rest)
+                } else {
+                    self.decode_insert(rest)
+                }
+            }
+            'U' => {
+                if self.streaming_prefix_active() {
+                    self.decode_stream_update(rest)
+                } else {
+                    self.decode_update(rest)
+                }
+            }
+            'D' => {
+                if self.streaming_prefix_active() {
+                    self.decode_stream_delete(rest)
+                } else {
+                    self.decode_delete(rest)
+                }
+            }
+            'S' => self.decode_stream_start(rest),
+            'E' => {
+                self.streaming = false;
+                Ok(Some(Change::StreamStop))
+            }
+            'c' => self.decode_stream_commit(rest),
+            'A' => self.decode_stream_abort(rest),
+            'T' => self.decode_truncate(rest),
+            'O' => decode_origin(rest),
+            'Y' => self.decode_type(rest),
+            'M' => decode_logical_message(rest),
+            'b' => decode_begin_prepare(rest),
+            'P' => decode_prepare(rest),
+            'K' => decode_commit_prepared(rest),
+            'r' => decode_rollback_prepared(rest),
+            'p' => decode_stream_prepare(rest),
+            _ => {
+                eprintln!("Unknown message type: {}", msg_type);
+                Ok(None)
+            }
+        }
+    }
+
+    fn decode_stream_start(&mut self, data: &[u8]) -> Result<Option<Change>> {
+        if data.len() < 5 {
+            return Err(anyhow!("Invalid Stream Start message length"));
+        }
+
+        let xid = u32::from_be_bytes(data[0..4].try_into()?);
+        let first_segment = data[4] != 0;
+
+        self.streaming = true;
+
+        Ok(Some(Change::StreamStart { xid, first_segment }))
+    }
+
+    fn decode_stream_commit(&mut self, data: &[u8]) -> Result<Option<Change>> {
+        if data.len() < 29 {
+            return Err(anyhow!("Invalid Stream Commit message length"));
+        }
+
+        let xid = u32::from_be_bytes(data[0..4].try_into()?);
+        let _flags = data[4];
+        let commit_lsn = u64::from_be_bytes(data[5..13].try_into()?);
+        let end_lsn = u64::from_be_bytes(data[13..21].try_into()?);
+        let timestamp = i64::from_be_bytes(data[21..29].try_into()?);
+
+        self.streaming = false;
+
+        Ok(Some(Change::StreamCommit {
+            xid,
+            commit_lsn: format_lsn_components(commit_lsn),
+            end_lsn: format_lsn_components(end_lsn),
+            timestamp,
+        }))
+    }
+
+    fn decode_stream_abort(&mut self, data: &[u8]) -> Result<Option<Change>> {
+        if data.len() < 8 {
+            return Err(anyhow!("Invalid Stream Abort message length"));
+        }
+
+        let xid = u32::from_be_bytes(data[0..4].try_into()?);
+        let subxid = u32::from_be_bytes(data[4..8].try_into()?);
+
+        self.streaming = false;
+
+        Ok(Some(Change::StreamAbort { xid, subxid }))
+    }
+
+    fn decode_stream_insert(&self, data: &[u8]) -> Result<Option<Change>> {
+        if data.len() < 4 {
+            return Err(anyhow!("Invalid streamed INSERT message length"));
+        }
+        let xid = u32::from_be_bytes(data[0..4].try_into()?);
+
+        match self.decode_insert(&data[4..])? {
+            Some(Change::Insert { relation_id, schema, table, new_tuple }) => {
+                Ok(Some(Change::StreamInsert { xid, relation_id, schema, table, new_tuple }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn decode_stream_update(&self, data: &[u8]) -> Result<Option<Change>> {
+        if data.len() < 4 {
+            return Err(anyhow!("Invalid streamed UPDATE message length"));
+        }
+        let xid = u32::from_be_bytes(data[0..4].try_into()?);
+
+        match self.decode_update(&data[4..])? {
+            Some(Change::Update { relation_id, schema, table, old_tuple, new_tuple }) => {
+                Ok(Some(Change::StreamUpdate { xid, relation_id, schema, table, old_tuple, new_tuple }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn decode_stream_delete(&self, data: &[u8]) -> Result<Option<Change>> {
+        if data.len() < 4 {
+            return Err(anyhow!("Invalid streamed DELETE message length"));
+        }
+        let xid = u32::from_be_bytes(data[0..4].try_into()?);
+
+        match self.decode_delete(&data[4..])? {
+            Some(Change::Delete { relation_id, schema, table, old_tuple }) => {
+                Ok(Some(Change::StreamDelete { xid, relation_id, schema, table, old_tuple }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn decode_relation(&mut self, data: &[u8]) -> Result<Option<Change>> {
+        let mut pos = 0;
+
+        let relation_id = u32::from_be_bytes(data[pos..pos + 4].try_into()?);
+        pos += 4;
+
+        let schema = read_string(data, &mut pos)?;
+        let table = read_string(data, &mut pos)?;
+
+        let _replica_identity = data[pos];
         pos += 1;
 
-        let name = read_string(data, &mut pos)?;
+        let column_count = u16::from_be_bytes(data[pos..pos + 2].try_into()?) as usize;
+        pos += 2;
+
+        let mut columns = Vec::new();
+        for _ in 0..column_count {
+            let _flags = data[pos];
+            pos += 1;
+
+            let name = read_string(data, &mut pos)?;
+
+            let type_id = u32::from_be_bytes(data[pos..pos + 4].try_into()?);
+            pos += 4;
+
+            let _type_modifier = i32::from_be_bytes(data[pos..pos + 4].try_into()?);
+            pos += 4;
+
+            columns.push(ColumnInfo {
+                name,
+                type_id,
+                flags: _flags,
+            });
+        }
+
+        self.relation_cache.insert(relation_id, (schema.clone(), table.clone(), columns.clone()));
+
+        Ok(Some(Change::Relation {
+            relation_id,
+            schema,
+            table,
+            columns,
+        }))
+    }
+
+    fn decode_insert(&self, data: &[u8]) -> Result<Option<Change>> {
+        let mut pos = 0;
 
-        let type_id = u32::from_be_bytes(data[pos..pos + 4].try_into()?);
+        let relation_id = u32::from_be_bytes(data[pos..pos + 4].try_into()?);
         pos += 4;
 
-        let _type_modifier = i32::from_be_bytes(data[pos..pos + 4].try_into()?);
+        let tuple_type = data[pos] as char;
+        pos += 1;
+
+        if tuple_type != 'N' {
+            return Err(anyhow!("Expected 'N' (new tuple) in INSERT"));
+        }
+
+        let new_tuple = self.decode_tuple(data, &mut pos, relation_id)?;
+
+        let (schema, table, _) = self
+            .relation_cache
+            .get(&relation_id)
+            .ok_or_else(|| anyhow!("Relation {} not found in cache", relation_id))?
+            .clone();
+
+        Ok(Some(Change::Insert {
+            relation_id,
+            schema,
+            table,
+            new_tuple,
+        }))
+    }
+
+    fn decode_update(&self, data: &[u8]) -> Result<Option<Change>> {
+        let mut pos = 0;
+
+        let relation_id = u32::from_be_bytes(data[pos..pos + 4].try_into()?);
         pos += 4;
 
-        columns.push(ColumnInfo {
-            name,
-            type_id,
-            flags: _flags,
-        });
+        let tuple_type = data[pos] as char;
+        pos += 1;
+
+        let old_tuple = if tuple_type == 'K' || tuple_type == 'O' {
+            let tuple = self.decode_tuple(data, &mut pos, relation_id)?;
+            let next_type = data[pos] as char;
+            pos += 1;
+            if next_type != 'N' {
+                return Err(anyhow!("Expected 'N' after old tuple in UPDATE"));
+            }
+            Some(tuple)
+        } else if tuple_type == 'N' {
+            None
+        } else {
+            return Err(anyhow!("Unexpected tuple type in UPDATE: {}", tuple_type));
+        };
+
+        let new_tuple = self.decode_tuple(data, &mut pos, relation_id)?;
+
+        let (schema, table, _) = self
+            .relation_cache
+            .get(&relation_id)
+            .ok_or_else(|| anyhow!("Relation {} not found in cache", relation_id))?
+            .clone();
+
+        Ok(Some(Change::Update {
+            relation_id,
+            schema,
+            table,
+            old_tuple,
+            new_tuple,
+        }))
     }
 
-    // Cache the relation info
-    let mut cache = RELATION_CACHE.lock().unwrap();
-    cache.insert(relation_id, (schema.clone(), table.clone(), columns.clone()));
-    drop(cache);
+    fn decode_delete(&self, data: &[u8]) -> Result<Option<Change>> {
+        let mut pos = 0;
 
-    Ok(Some(Change::Relation {
-        relation_id,
-        schema,
-        table,
-        columns,
-    }))
+        let relation_id = u32::from_be_bytes(data[pos..pos + 4].try_into()?);
+        pos += 4;
+
+        let tuple_type = data[pos] as char;
+        pos += 1;
+
+        if tuple_type != 'K' && tuple_type != 'O' {
+            return Err(anyhow!("Expected 'K' or 'O' (old tuple) in DELETE"));
+        }
+
+        let old_tuple = self.decode_tuple(data, &mut pos, relation_id)?;
+
+        let (schema, table, _) = self
+            .relation_cache
+            .get(&relation_id)
+            .ok_or_else(|| anyhow!("Relation {} not found in cache", relation_id))?
+            .clone();
+
+        Ok(Some(Change::Delete {
+            relation_id,
+            schema,
+            table,
+            old_tuple,
+        }))
+    }
+
+    fn decode_tuple(&self, data: &[u8], pos: &mut usize, relation_id: u32) -> Result<IndexMap<String, Option<String>>> {
+        let column_count = u16::from_be_bytes(data[*pos..*pos + 2].try_into()?) as usize;
+        *pos += 2;
+
+        let (_, _, columns) = self
+            .relation_cache
+            .get(&relation_id)
+            .ok_or_else(|| anyhow!("Relation {} not found in cache", relation_id))?;
+        let columns = columns.clone();
+
+        // Columns are inserted in the order the RELATION message declared
+        // them (the loop below walks `0..column_count` in wire order), so
+        // the IndexMap's iteration order matches it too.
+        let mut tuple = IndexMap::new();
+
+        for i in 0..column_count {
+            let column_name = if i < columns.len() {
+                columns[i].name.clone()
+            } else {
+                format!("column_{}", i)
+            };
+
+            let tuple_type = data[*pos] as char;
+            *pos += 1;
+
+            match tuple_type {
+                'n' => {
+                    tuple.insert(column_name, None); // NULL
+                }
+                'u' => {
+                    // UNCHANGED TOASTed value: the column wasn't sent at all, so
+                    // leave it out of the map entirely. `tuple.get(name)` then
+                    // returns `None` (key absent, "value not sent") which merge
+                    // logic can tell apart from `Some(&None)` (key present,
+                    // explicit NULL) - unlike collapsing both into `None`, this
+                    // doesn't lose the "leave the prior value intact" signal.
+                }
+                't' => {
+                    // Text value
+                    let length = u32::from_be_bytes(data[*pos..*pos + 4].try_into()?) as usize;
+                    *pos += 4;
+                    let value = String::from_utf8_lossy(&data[*pos..*pos + length]).to_string();
+                    *pos += length;
+                    tuple.insert(column_name, Some(value));
+                }
+                'b' => {
+                    // Binary value (subscription requested `binary = true`).
+                    // Decode fixed-width representations (network-order ints,
+                    // IEEE floats) according to the column's OID, the same way
+                    // the text path keeps an `Option<String>` shape; anything
+                    // without a fixed-width decode falls back to hex bytes.
+                    let length = u32::from_be_bytes(data[*pos..*pos + 4].try_into()?) as usize;
+                    *pos += 4;
+                    let raw = &data[*pos..*pos + length];
+                    *pos += length;
+
+                    let type_id = columns.get(i).map(|c| c.type_id);
+                    let text = type_id
+                        .and_then(pg_type::lookup)
+                        .map(|pg_type| binary_to_text(pg_type, raw))
+                        .or_else(|| {
+                            // Custom/enum types have no fixed-width binary
+                            // encoding of their own - Postgres sends their
+                            // text label as UTF-8 bytes the same as the 't'
+                            // (text) tuple format would, so decode it the
+                            // same way instead of falling through to the
+                            // opaque hex fallback below.
+                            type_id
+                                .filter(|id| self.get_custom_type(*id).is_some())
+                                .map(|_| String::from_utf8_lossy(raw).to_string())
+                        })
+                        .unwrap_or_else(|| format!("\\x{}", hex_encode(raw)));
+                    tuple.insert(column_name, Some(text));
+                }
+                other => {
+                    return Err(anyhow!("Unknown tuple column type: {}", other));
+                }
+            }
+        }
+
+        Ok(tuple)
+    }
+
+    fn decode_truncate(&self, data: &[u8]) -> Result<Option<Change>> {
+        if data.len() < 5 {
+            return Err(anyhow!("Invalid Truncate message length"));
+        }
+
+        let relation_count = u32::from_be_bytes(data[0..4].try_into()?) as usize;
+        let option_bits = data[4];
+        let cascade = option_bits & 0b01 != 0;
+        let restart_identity = option_bits & 0b10 != 0;
+
+        let mut pos = 5;
+        let mut relations = Vec::with_capacity(relation_count);
+        for _ in 0..relation_count {
+            if data.len() < pos + 4 {
+                return Err(anyhow!("Truncate message truncated before relation_count ids were read"));
+            }
+            let relation_id = u32::from_be_bytes(data[pos..pos + 4].try_into()?);
+            pos += 4;
+
+            let (schema, table, _) = self
+                .relation_cache
+                .get(&relation_id)
+                .ok_or_else(|| anyhow!("Relation {} not found in cache", relation_id))?;
+            relations.push((schema.clone(), table.clone()));
+        }
+
+        Ok(Some(Change::Truncate { relations, cascade, restart_identity }))
+    }
+
+    fn decode_type(&mut self, data: &[u8]) -> Result<Option<Change>> {
+        if data.len() < 4 {
+            return Err(anyhow!("Invalid Type message length"));
+        }
+
+        let type_id = u32::from_be_bytes(data[0..4].try_into()?);
+        let mut pos = 4;
+        let namespace = read_string(data, &mut pos)?;
+        let name = read_string(data, &mut pos)?;
+
+        self.custom_types.insert(type_id, (namespace.clone(), name.clone()));
+
+        Ok(Some(Change::Type { type_id, namespace, name }))
+    }
 }
 
-fn decode_insert(data: &[u8]) -> Result<Option<Change>> {
-    let mut pos = 0;
+thread_local! {
+    /// Backs the free-function decode API below, for callers that decode a
+    /// single stream on one thread and don't need their own `Decoder`.
+    static DEFAULT_DECODER: RefCell<Decoder> = RefCell::new(Decoder::new());
+}
 
-    let relation_id = u32::from_be_bytes(data[pos..pos + 4].try_into()?);
-    pos += 4;
+/// Thin wrapper over a default thread-local `Decoder`. Prefer constructing
+/// your own `Decoder` when running more than one replication stream in the
+/// same process.
+pub fn decode_pgoutput_message(data: &[u8]) -> Result<Option<Change>> {
+    DEFAULT_DECODER.with(|decoder| decoder.borrow_mut().decode_message(data))
+}
+
+/// Get column metadata for a relation from the default thread-local decoder.
+pub fn get_relation_columns(relation_id: u32) -> Option<Vec<ColumnInfo>> {
+    DEFAULT_DECODER.with(|decoder| decoder.borrow().get_relation_columns(relation_id))
+}
+
+/// Record the `proto_version` negotiated with `START_REPLICATION` on the
+/// default thread-local decoder.
+pub fn set_protocol_version(version: u8) {
+    DEFAULT_DECODER.with(|decoder| decoder.borrow_mut().set_protocol_version(version));
+}
+
+/// Look up a custom (non-built-in) type's `namespace.name` from the default
+/// thread-local decoder.
+pub fn get_custom_type(type_id: u32) -> Option<(String, String)> {
+    DEFAULT_DECODER.with(|decoder| decoder.borrow().get_custom_type(type_id))
+}
+
+fn decode_origin(data: &[u8]) -> Result<Option<Change>> {
+    if data.len() < 8 {
+        return Err(anyhow!("Invalid Origin message length"));
+    }
 
-    let tuple_type = data[pos] as char;
-    pos += 1;
+    let commit_lsn = u64::from_be_bytes(data[0..8].try_into()?);
+    let mut pos = 8;
+    let name = read_string(data, &mut pos)?;
 
-    if tuple_type != 'N' {
-        return Err(anyhow!("Expected 'N' (new tuple) in INSERT"));
+    Ok(Some(Change::Origin { commit_lsn: format_lsn_components(commit_lsn), name }))
+}
+
+fn decode_logical_message(data: &[u8]) -> Result<Option<Change>> {
+    if data.len() < 9 {
+        return Err(anyhow!("Invalid Logical Message length"));
     }
 
-    let new_tuple = decode_tuple(data, &mut pos, relation_id)?;
+    let transactional = data[0] != 0;
+    let lsn = u64::from_be_bytes(data[1..9].try_into()?);
+    let mut pos = 9;
+    let prefix = read_string(data, &mut pos)?;
+
+    if data.len() < pos + 4 {
+        return Err(anyhow!("Logical Message missing content length"));
+    }
+    let length = u32::from_be_bytes(data[pos..pos + 4].try_into()?) as usize;
+    pos += 4;
 
-    let cache = RELATION_CACHE.lock().unwrap();
-    let (schema, table, _) = cache
-        .get(&relation_id)
-        .ok_or_else(|| anyhow!("Relation {} not found in cache", relation_id))?
-        .clone();
-    drop(cache);
+    if data.len() < pos + length {
+        return Err(anyhow!("Logical Message content shorter than declared length"));
+    }
+    let content = data[pos..pos + length].to_vec();
 
-    Ok(Some(Change::Insert {
-        relation_id,
-        schema,
-        table,
-        new_tuple,
+    Ok(Some(Change::LogicalMessage {
+        transactional,
+        lsn: format_lsn_components(lsn),
+        prefix,
+        content,
     }))
 }
 
-fn decode_update(data: &[u8]) -> Result<Option<Change>> {
-    let mut pos = 0;
+/// Format a raw 64-bit LSN as Postgres's `"X/Y"` hex notation.
+fn format_lsn_components(lsn: u64) -> String {
+    Lsn::from(lsn).to_string()
+}
 
-    let relation_id = u32::from_be_bytes(data[pos..pos + 4].try_into()?);
-    pos += 4;
+/// Shared wire layout for Begin Prepare, Prepare, Commit Prepared and Stream
+/// Prepare: two LSNs (8 bytes each), a timestamp(8), the xid(4), then a
+/// null-terminated GID. Only the field names differ per message type.
+fn decode_prepare_fields(data: &[u8]) -> Result<(u64, u64, i64, u32, String)> {
+    if data.len() < 28 {
+        return Err(anyhow!("Invalid prepared-transaction message length"));
+    }
 
-    let tuple_type = data[pos] as char;
-    pos += 1;
+    let lsn_a = u64::from_be_bytes(data[0..8].try_into()?);
+    let lsn_b = u64::from_be_bytes(data[8..16].try_into()?);
+    let timestamp = i64::from_be_bytes(data[16..24].try_into()?);
+    let xid = u32::from_be_bytes(data[24..28].try_into()?);
+    let mut pos = 28;
+    let gid = read_string(data, &mut pos)?;
 
-    let old_tuple = if tuple_type == 'K' || tuple_type == 'O' {
-        let tuple = decode_tuple(data, &mut pos, relation_id)?;
-        let next_type = data[pos] as char;
-        pos += 1;
-        if next_type != 'N' {
-            return Err(anyhow!("Expected 'N' after old tuple in UPDATE"));
-        }
-        Some(tuple)
-    } else if tuple_type == 'N' {
-        None
-    } else {
-        return Err(anyhow!("Unexpected tuple type in UPDATE: {}", tuple_type));
-    };
+    Ok((lsn_a, lsn_b, timestamp, xid, gid))
+}
+
+fn decode_begin_prepare(data: &[u8]) -> Result<Option<Change>> {
+    let (prepare_lsn, end_lsn, prepare_timestamp, xid, gid) = decode_prepare_fields(data)?;
 
-    let new_tuple = decode_tuple(data, &mut pos, relation_id)?;
-
-    let cache = RELATION_CACHE.lock().unwrap();
-    let (schema, table, _) = cache
-        .get(&relation_id)
-        .ok_or_else(|| anyhow!("Relation {} not found in cache", relation_id))?
-        .clone();
-    drop(cache);
-
-    Ok(Some(Change::Update {
-        relation_id,
-        schema,
-        table,
-        old_tuple,
-        new_tuple,
+    Ok(Some(Change::BeginPrepare {
+        prepare_lsn: format_lsn_components(prepare_lsn),
+        end_lsn: format_lsn_components(end_lsn),
+        prepare_timestamp,
+        xid,
+        gid,
     }))
 }
 
-fn decode_delete(data: &[u8]) -> Result<Option<Change>> {
-    let mut pos = 0;
+fn decode_prepare(data: &[u8]) -> Result<Option<Change>> {
+    let (prepare_lsn, end_lsn, prepare_timestamp, xid, gid) = decode_prepare_fields(data)?;
 
-    let relation_id = u32::from_be_bytes(data[pos..pos + 4].try_into()?);
-    pos += 4;
+    Ok(Some(Change::Prepare {
+        prepare_lsn: format_lsn_components(prepare_lsn),
+        end_lsn: format_lsn_components(end_lsn),
+        prepare_timestamp,
+        xid,
+        gid,
+    }))
+}
 
-    let tuple_type = data[pos] as char;
-    pos += 1;
+fn decode_stream_prepare(data: &[u8]) -> Result<Option<Change>> {
+    let (prepare_lsn, end_lsn, prepare_timestamp, xid, gid) = decode_prepare_fields(data)?;
 
-    if tuple_type != 'K' && tuple_type != 'O' {
-        return Err(anyhow!("Expected 'K' or 'O' (old tuple) in DELETE"));
-    }
+    Ok(Some(Change::StreamPrepare {
+        prepare_lsn: format_lsn_components(prepare_lsn),
+        end_lsn: format_lsn_components(end_lsn),
+        prepare_timestamp,
+        xid,
+        gid,
+    }))
+}
 
-    let old_tuple = decode_tuple(data, &mut pos, relation_id)?;
+fn decode_commit_prepared(data: &[u8]) -> Result<Option<Change>> {
+    let (commit_lsn, end_lsn, commit_timestamp, xid, gid) = decode_prepare_fields(data)?;
 
-    let cache = RELATION_CACHE.lock().unwrap();
-    let (schema, table, _) = cache
-        .get(&relation_id)
-        .ok_or_else(|| anyhow!("Relation {} not found in cache", relation_id))?
-        .clone();
-    drop(cache);
+    Ok(Some(Change::CommitPrepared {
+        commit_lsn: format_lsn_components(commit_lsn),
+        end_lsn: format_lsn_components(end_lsn),
+        commit_timestamp,
+        xid,
+        gid,
+    }))
+}
+
+fn decode_rollback_prepared(data: &[u8]) -> Result<Option<Change>> {
+    if data.len() < 36 {
+        return Err(anyhow!("Invalid Rollback Prepared message length"));
+    }
 
-    Ok(Some(Change::Delete {
-        relation_id,
-        schema,
-        table,
-        old_tuple,
+    let prepare_end_lsn = u64::from_be_bytes(data[0..8].try_into()?);
+    let rollback_end_lsn = u64::from_be_bytes(data[8..16].try_into()?);
+    let prepare_timestamp = i64::from_be_bytes(data[16..24].try_into()?);
+    let rollback_timestamp = i64::from_be_bytes(data[24..32].try_into()?);
+    let xid = u32::from_be_bytes(data[32..36].try_into()?);
+    let mut pos = 36;
+    let gid = read_string(data, &mut pos)?;
+
+    Ok(Some(Change::RollbackPrepared {
+        prepare_end_lsn: format_lsn_components(prepare_end_lsn),
+        rollback_end_lsn: format_lsn_components(rollback_end_lsn),
+        prepare_timestamp,
+        rollback_timestamp,
+        xid,
+        gid,
     }))
 }
 
-fn decode_tuple(
-    data: &[u8],
-    pos: &mut usize,
-    relation_id: u32,
-) -> Result<HashMap<String, Option<String>>> {
-    let column_count = u16::from_be_bytes(data[*pos..*pos + 2].try_into()?) as usize;
-    *pos += 2;
-
-    let cache = RELATION_CACHE.lock().unwrap();
-    let (_, _, columns) = cache
-        .get(&relation_id)
-        .ok_or_else(|| anyhow!("Relation {} not found in cache", relation_id))?;
-    let columns = columns.clone();
-    drop(cache);
-
-    let mut tuple = HashMap::new();
-
-    for i in 0..column_count {
-        let column_name = if i < columns.len() {
-            columns[i].name.clone()
-        } else {
-            format!("column_{}", i)
-        };
+fn decode_begin(data: &[u8]) -> Result<Option<Change>> {
+    if data.len() < 20 {
+        return Err(anyhow!("Invalid BEGIN message length"));
+    }
 
-        let tuple_type = data[*pos] as char;
-        *pos += 1;
+    let lsn = u64::from_be_bytes(data[0..8].try_into()?);
+    let timestamp = i64::from_be_bytes(data[8..16].try_into()?);
+    let xid = u32::from_be_bytes(data[16..20].try_into()?);
 
-        let value = match tuple_type {
-            'n' => None, // NULL
-            'u' => None, // UNCHANGED (for UPDATE)
-            't' => {
-                // Text value
-                let length = u32::from_be_bytes(data[*pos..*pos + 4].try_into()?) as usize;
-                *pos += 4;
-                let value = String::from_utf8_lossy(&data[*pos..*pos + length]).to_string();
-                *pos += length;
-                Some(value)
-            }
-            _ => {
-                return Err(anyhow!("Unknown tuple data type: {}", tuple_type));
-            }
-        };
+    Ok(Some(Change::Begin {
+        lsn: Lsn::from(lsn).to_string(),
+        timestamp,
+        xid,
+    }))
+}
 
-        tuple.insert(column_name, value);
+fn decode_commit(data: &[u8]) -> Result<Option<Change>> {
+    if data.len() < 17 {
+        return Err(anyhow!("Invalid COMMIT message length"));
     }
 
-    Ok(tuple)
+    let _flags = data[0];
+    let lsn = u64::from_be_bytes(data[1..9].try_into()?);
+    let _end_lsn = u64::from_be_bytes(data[9..17].try_into()?);
+    let timestamp = i64::from_be_bytes(data[17..25].try_into()?);
+
+    Ok(Some(Change::Commit {
+        lsn: Lsn::from(lsn).to_string(),
+        timestamp,
+    }))
+}
+
+/// Decode a fixed-width binary column value into the same text
+/// representation the text-format protocol path would have produced, so
+/// `Option<String>` consumers see one consistent shape regardless of which
+/// wire format the column arrived in. Types without a fixed-width binary
+/// layout handled here (e.g. numeric, date/timestamp) fall back to hex.
+fn binary_to_text(pg_type: PgType, raw: &[u8]) -> String {
+    let parsed = match pg_type {
+        PgType::Bool => raw.first().map(|b| if *b != 0 { "t".to_string() } else { "f".to_string() }),
+        PgType::Int2 => raw.try_into().ok().map(|b: [u8; 2]| i16::from_be_bytes(b).to_string()),
+        PgType::Int4 => raw.try_into().ok().map(|b: [u8; 4]| i32::from_be_bytes(b).to_string()),
+        PgType::Int8 => raw.try_into().ok().map(|b: [u8; 8]| i64::from_be_bytes(b).to_string()),
+        PgType::Float4 => raw.try_into().ok().map(|b: [u8; 4]| f32::from_be_bytes(b).to_string()),
+        PgType::Float8 => raw.try_into().ok().map(|b: [u8; 8]| f64::from_be_bytes(b).to_string()),
+        PgType::Text | PgType::Json => Some(String::from_utf8_lossy(raw).to_string()),
+        PgType::Bytea | PgType::Numeric | PgType::Uuid | PgType::Date | PgType::Timestamp | PgType::Timestamptz => None,
+    };
+
+    parsed.unwrap_or_else(|| format!("\\x{}", hex_encode(raw)))
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
 
 fn read_string(data: &[u8], pos: &mut usize) -> Result<String> {
@@ -0,0 +1,180 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::decoder::{Change, Decoder};
+use crate::lsn::Lsn;
+use crate::output::OutputTarget;
+
+const CHECKPOINT_KEY: &[u8] = b"last_committed_lsn";
+
+/// Durable, resumable `OutputTarget` backed by an embedded `sled` database.
+///
+/// Every change is appended to a `changes` tree keyed by `(sort_key, sequence)`
+/// — `sort_key` is the change's own LSN for `Begin`/`Commit`/`StreamCommit`,
+/// or the enclosing transaction's LSN (tracked from the last `Begin` seen,
+/// the same approach `StdoutOutput`'s Debezium mode uses) for the row
+/// changes inside it, since those don't carry an LSN of their own.
+/// Streamed (in-progress) transactions are opened by `StreamStart` rather
+/// than `Begin` and don't get a real LSN until their matching
+/// `StreamCommit`, so their `StreamInsert`/`StreamUpdate`/`StreamDelete`
+/// rows are sorted under a synthetic key tagging the streamed transaction's
+/// `xid` instead (the top bit is set so it can never collide with a real
+/// LSN). `sequence` is a monotonic counter breaking ties between changes
+/// sharing a sort key so they sort in write order. `Change::Commit` advances
+/// a `last_committed_lsn` checkpoint in a separate `checkpoint` tree and
+/// prunes every change up to and including that LSN from `changes`, since
+/// once a transaction commits there's nothing left to replay for it;
+/// `Change::StreamCommit` does the same, and additionally prunes by the
+/// streamed transaction's `xid` tag, since its rows were never LSN-keyed. The two trees are kept apart so pruning a numeric LSN range
+/// can never collide with the checkpoint's own key.
+///
+/// Unlike `CheckpointStore` (which only remembers a position), this target
+/// also retains the unacknowledged change bodies themselves, so a crashed
+/// process can replay them via `unacknowledged()` instead of only knowing
+/// where to resume.
+pub struct DurableOutput {
+    changes: sled::Tree,
+    checkpoint: sled::Tree,
+    current_txn_lsn: Mutex<Option<Lsn>>,
+    /// The `xid` of the streamed (in-progress) transaction currently being
+    /// spilled via `StreamStart`/`StreamInsert`/.../`StreamCommit`, if any.
+    /// Cleared on any `StreamCommit`, and on a top-level `StreamAbort` (one
+    /// whose `subxid` equals its `xid`).
+    current_stream_xid: Mutex<Option<u32>>,
+    sequence: AtomicU64,
+}
+
+/// Tags a streamed transaction's synthetic sort key so it can never collide
+/// with a real LSN (which would need to exceed 2^63 to set this bit).
+const STREAM_XID_TAG: u64 = 1 << 63;
+
+fn stream_sort_key(xid: u32) -> u64 {
+    STREAM_XID_TAG | xid as u64
+}
+
+impl DurableOutput {
+    pub fn open(dir: &Path) -> Result<Self> {
+        let db = sled::open(dir)?;
+        let changes = db.open_tree("changes")?;
+        let checkpoint = db.open_tree("checkpoint")?;
+        Ok(Self {
+            changes,
+            checkpoint,
+            current_txn_lsn: Mutex::new(None),
+            current_stream_xid: Mutex::new(None),
+            sequence: AtomicU64::new(0),
+        })
+    }
+
+    /// The last LSN recorded via a `Change::Commit` or `Change::StreamCommit`, i.e. the position a
+    /// replication client should request to resume from after a restart.
+    pub fn resume_from(&self) -> Result<Option<Lsn>> {
+        match self.checkpoint.get(CHECKPOINT_KEY)? {
+            Some(value) => Ok(Some(Lsn(u64::from_be_bytes(value.as_ref().try_into()?)))),
+            None => Ok(None),
+        }
+    }
+
+    /// Changes buffered but not yet pruned by a later commit, oldest first.
+    pub fn unacknowledged(&self) -> impl Iterator<Item = Result<Change>> + '_ {
+        self.changes.iter().map(|entry| {
+            let (_, value) = entry?;
+            Ok(serde_json::from_slice(&value)?)
+        })
+    }
+
+    fn next_key(&self, sort_key: u64) -> [u8; 16] {
+        let seq = self.sequence.fetch_add(1, Ordering::SeqCst);
+        let mut key = [0u8; 16];
+        key[..8].copy_from_slice(&sort_key.to_be_bytes());
+        key[8..].copy_from_slice(&seq.to_be_bytes());
+        key
+    }
+
+    /// Checkpoint `lsn` as the last committed position and prune every
+    /// `changes` entry sorted at or before it - used by both `Commit` (whose
+    /// rows were keyed by LSN) and `StreamCommit` (whose checkpoint is a real
+    /// LSN even though its own rows were keyed by xid and pruned separately).
+    fn checkpoint_lsn(&self, lsn: Lsn) -> Result<()> {
+        self.checkpoint.insert(CHECKPOINT_KEY, &lsn.0.to_be_bytes())?;
+
+        let mut upper = [0xFFu8; 16];
+        upper[..8].copy_from_slice(&lsn.0.to_be_bytes());
+        let stale: Vec<_> = self.changes.range(..=upper.as_slice()).keys().collect::<std::result::Result<_, _>>()?;
+        for key in stale {
+            self.changes.remove(key)?;
+        }
+        Ok(())
+    }
+
+    /// Remove every `changes` entry sorted under the given streamed
+    /// transaction's synthetic xid tag, since `StreamCommit`'s own LSN was
+    /// never used to key those rows.
+    fn prune_stream_xid(&self, xid: u32) -> Result<()> {
+        let prefix = stream_sort_key(xid).to_be_bytes();
+        let stale: Vec<_> = self.changes.scan_prefix(prefix).keys().collect::<std::result::Result<_, _>>()?;
+        for key in stale {
+            self.changes.remove(key)?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl OutputTarget for DurableOutput {
+    async fn write_change(&self, change: &Change, _decoder: &Decoder) -> Result<()> {
+        if let Change::Begin { .. } = change {
+            *self.current_txn_lsn.lock().unwrap() = change.lsn_typed();
+        }
+        if let Change::StreamStart { xid, .. } = change {
+            *self.current_stream_xid.lock().unwrap() = Some(*xid);
+        }
+
+        let current_txn_lsn = *self.current_txn_lsn.lock().unwrap();
+        let current_stream_xid = *self.current_stream_xid.lock().unwrap();
+        let sort_key = change
+            .lsn_typed()
+            .map(|lsn| lsn.0)
+            .or_else(|| current_txn_lsn.map(|lsn| lsn.0))
+            .or_else(|| current_stream_xid.map(stream_sort_key));
+
+        if let Some(sort_key) = sort_key {
+            let key = self.next_key(sort_key);
+            self.changes.insert(key, serde_json::to_vec(change)?)?;
+        }
+
+        if let Change::Commit { .. } = change {
+            if let Some(lsn) = change.lsn_typed() {
+                self.checkpoint_lsn(lsn)?;
+                *self.current_txn_lsn.lock().unwrap() = None;
+            }
+        }
+
+        if let Change::StreamCommit { xid, .. } = change {
+            if let Some(lsn) = change.lsn_typed() {
+                self.checkpoint_lsn(lsn)?;
+                self.prune_stream_xid(*xid)?;
+            }
+            *self.current_stream_xid.lock().unwrap() = None;
+        }
+
+        if let Change::StreamAbort { xid, subxid } = change {
+            if xid == subxid {
+                self.prune_stream_xid(*xid)?;
+                *self.current_stream_xid.lock().unwrap() = None;
+            }
+        }
+
+        self.changes.flush()?;
+        self.checkpoint.flush()?;
+
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "durable"
+    }
+}
@@ -0,0 +1,146 @@
+use anyhow::Result;
+use std::collections::HashSet;
+
+use crate::decoder::Change;
+
+/// A `schema.table` rule where either half may be a `*` glob, e.g.
+/// `public.users` or `analytics.*`.
+#[derive(Debug, Clone)]
+struct TableRule {
+    schema: String,
+    table: String,
+}
+
+impl TableRule {
+    fn parse(spec: &str) -> Result<Self> {
+        let (schema, table) = spec
+            .split_once('.')
+            .ok_or_else(|| anyhow::anyhow!("Invalid table rule '{}', expected schema.table", spec))?;
+        Ok(Self { schema: schema.to_string(), table: table.to_string() })
+    }
+
+    fn matches(&self, schema: &str, table: &str) -> bool {
+        glob_match(&self.schema, schema) && glob_match(&self.table, table)
+    }
+}
+
+/// Minimal glob matcher supporting a single `*` wildcard anywhere in the
+/// pattern (e.g. `analytics.*`, `public.*_archive`); exact match otherwise.
+fn glob_match(pattern: &str, value: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == value,
+        Some((prefix, suffix)) => {
+            value.len() >= prefix.len() + suffix.len()
+                && value.starts_with(prefix)
+                && value.ends_with(suffix)
+        }
+    }
+}
+
+/// Decides which decoded changes reach the output targets, based on
+/// schema/table allow/deny lists and an allowed-operations set.
+pub struct ChangeFilter {
+    include_tables: Option<Vec<TableRule>>,
+    exclude_tables: Vec<TableRule>,
+    operations: Option<HashSet<String>>,
+}
+
+impl ChangeFilter {
+    pub fn new(
+        include_tables: Option<&str>,
+        exclude_tables: Option<&str>,
+        operations: Option<&str>,
+    ) -> Result<Self> {
+        let include_tables = include_tables
+            .map(|spec| spec.split(',').map(str::trim).map(TableRule::parse).collect())
+            .transpose()?;
+
+        let exclude_tables = exclude_tables
+            .map(|spec| spec.split(',').map(str::trim).map(TableRule::parse).collect())
+            .transpose()?
+            .unwrap_or_default();
+
+        let operations = operations.map(|spec| {
+            spec.split(',')
+                .map(|op| op.trim().to_lowercase())
+                .collect::<HashSet<_>>()
+        });
+
+        Ok(Self { include_tables, exclude_tables, operations })
+    }
+
+    fn table_allowed(&self, schema: &str, table: &str) -> bool {
+        if self.exclude_tables.iter().any(|rule| rule.matches(schema, table)) {
+            return false;
+        }
+        match &self.include_tables {
+            Some(rules) => rules.iter().any(|rule| rule.matches(schema, table)),
+            None => true,
+        }
+    }
+
+    fn operation_allowed(&self, operation: &str) -> bool {
+        match &self.operations {
+            Some(ops) => ops.contains(operation),
+            None => true,
+        }
+    }
+
+    /// Whether a data change (Insert/Update/Delete) should reach output
+    /// targets. Begin/Commit are handled separately by the caller: a
+    /// transaction's Begin/Commit markers are only forwarded when at least
+    /// one of its data changes does. Relation is table-scoped like the row
+    /// changes it describes, so an excluded table's Relation is filtered out
+    /// the same way its Insert/Update/Delete rows are - otherwise schema
+    /// metadata (and the column list/types that go with it) for a table a
+    /// consumer asked not to see would still reach every output target.
+    pub fn passes(&self, change: &Change) -> bool {
+        match change {
+            Change::Insert { schema, table, .. } => {
+                self.table_allowed(schema, table) && self.operation_allowed("insert")
+            }
+            Change::Update { schema, table, .. } => {
+                self.table_allowed(schema, table) && self.operation_allowed("update")
+            }
+            Change::Delete { schema, table, .. } => {
+                self.table_allowed(schema, table) && self.operation_allowed("delete")
+            }
+            Change::StreamInsert { schema, table, .. } => {
+                self.table_allowed(schema, table) && self.operation_allowed("insert")
+            }
+            Change::StreamUpdate { schema, table, .. } => {
+                self.table_allowed(schema, table) && self.operation_allowed("update")
+            }
+            Change::StreamDelete { schema, table, .. } => {
+                self.table_allowed(schema, table) && self.operation_allowed("delete")
+            }
+            Change::Relation { schema, table, .. } => self.table_allowed(schema, table),
+            // TRUNCATE is table-scoped like the row changes above, but can
+            // name several tables at once (`TRUNCATE a, b, c`) - it passes if
+            // at least one named table survives filtering, the same way a
+            // transaction's Begin/Commit passes if at least one of its
+            // changes does. The change itself is not narrowed: its
+            // `relations` still name every truncated table, excluded ones
+            // included, and `Change::subjects` fans it out to all of them.
+            Change::Truncate { relations, .. } => {
+                relations.iter().any(|(schema, table)| self.table_allowed(schema, table))
+            }
+            // Transaction/stream control messages and type-system metadata
+            // aren't table-scoped, so they always pass filtering.
+            Change::Begin { .. }
+            | Change::Commit { .. }
+            | Change::StreamStart { .. }
+            | Change::StreamStop
+            | Change::StreamCommit { .. }
+            | Change::StreamAbort { .. }
+            | Change::Origin { .. }
+            | Change::Type { .. }
+            | Change::LogicalMessage { .. }
+            | Change::BeginPrepare { .. }
+            | Change::Prepare { .. }
+            | Change::CommitPrepared { .. }
+            | Change::RollbackPrepared { .. }
+            | Change::StreamPrepare { .. } => true,
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+use tonic::{transport::Server, Request, Response, Status};
+
+use crate::decoder::{Change, Decoder};
+use crate::output::OutputTarget;
+
+pub mod proto {
+    tonic::include_proto!("pgoutput");
+}
+
+use proto::change_stream_server::{ChangeStream, ChangeStreamServer};
+use proto::{Begin, ChangeEvent, Column, Commit, ColumnValue, Relation, RowChange, SubscribeRequest};
+
+/// Bounded per-subscriber buffer. A subscriber that can't keep up (its
+/// buffer is full when `try_send` is called) is disconnected and removed,
+/// rather than stalling the replication loop for every other subscriber.
+const SUBSCRIBER_CHANNEL_CAPACITY: usize = 1024;
+
+type SubscriberId = u64;
+
+#[derive(Default)]
+struct Subscribers {
+    next_id: SubscriberId,
+    senders: HashMap<SubscriberId, mpsc::Sender<Result<ChangeEvent, Status>>>,
+}
+
+struct ChangeStreamService {
+    subscribers: std::sync::Arc<Mutex<Subscribers>>,
+}
+
+#[tonic::async_trait]
+impl ChangeStream for ChangeStreamService {
+    type SubscribeStream = tokio_stream::wrappers::ReceiverStream<Result<ChangeEvent, Status>>;
+
+    async fn subscribe(
+        &self,
+        _request: Request<SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let (tx, rx) = mpsc::channel(SUBSCRIBER_CHANNEL_CAPACITY);
+
+        let mut subscribers = self.subscribers.lock().unwrap();
+        let id = subscribers.next_id;
+        subscribers.next_id += 1;
+        subscribers.senders.insert(id, tx);
+
+        Ok(Response::new(tokio_stream::wrappers::ReceiverStream::new(rx)))
+    }
+}
+
+/// Streams decoded changes to any number of gRPC subscribers connected via
+/// the `ChangeStream/Subscribe` RPC.
+pub struct GrpcOutput {
+    subscribers: std::sync::Arc<Mutex<Subscribers>>,
+}
+
+impl GrpcOutput {
+    pub async fn new(listen_addr: &str) -> Result<Self> {
+        let addr: SocketAddr = listen_addr.parse()?;
+        let subscribers = std::sync::Arc::new(Mutex::new(Subscribers::default()));
+        let service = ChangeStreamService {
+            subscribers: subscribers.clone(),
+        };
+
+        tokio::spawn(async move {
+            if let Err(e) = Server::builder()
+                .add_service(ChangeStreamServer::new(service))
+                .serve(addr)
+                .await
+            {
+                eprintln!("gRPC server error: {}", e);
+            }
+        });
+
+        Ok(Self { subscribers })
+    }
+}
+
+#[async_trait]
+impl OutputTarget for GrpcOutput {
+    async fn write_change(&self, change: &Change, _decoder: &Decoder) -> Result<()> {
+        let Some(event) = to_proto_event(change) else {
+            // Stream-control, truncate, two-phase and type-system messages have
+            // no proto representation yet.
+            return Ok(());
+        };
+
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.senders.retain(|_, sender| {
+            // `try_send` fails immediately (Full or Closed) instead of awaiting,
+            // so one slow or disconnected subscriber never blocks the replication
+            // loop or the other subscribers.
+            sender.try_send(Ok(event.clone())).is_ok()
+        });
+
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "grpc"
+    }
+}
+
+fn to_column_values(tuple: &indexmap::IndexMap<String, Option<String>>) -> Vec<ColumnValue> {
+    tuple
+        .iter()
+        .map(|(name, value)| ColumnValue {
+            name: name.clone(),
+            is_null: value.is_none(),
+            value: value.clone().unwrap_or_default(),
+        })
+        .collect()
+}
+
+fn to_proto_event(change: &Change) -> Option<ChangeEvent> {
+    use proto::change_event::Event;
+
+    let event = match change {
+        Change::Begin { lsn, timestamp, xid } => Event::Begin(Begin {
+            lsn: lsn.clone(),
+            timestamp: *timestamp,
+            xid: *xid,
+        }),
+        Change::Commit { lsn, timestamp } => Event::Commit(Commit {
+            lsn: lsn.clone(),
+            timestamp: *timestamp,
+        }),
+        Change::Relation { relation_id, schema, table, columns } => Event::Relation(Relation {
+            relation_id: *relation_id,
+            schema: schema.clone(),
+            table: table.clone(),
+            columns: columns
+                .iter()
+                .map(|c| Column {
+                    name: c.name.clone(),
+                    type_id: c.type_id,
+                    flags: c.flags as u32,
+                })
+                .collect(),
+        }),
+        Change::Insert { relation_id, schema, table, new_tuple } => Event::Insert(RowChange {
+            relation_id: *relation_id,
+            schema: schema.clone(),
+            table: table.clone(),
+            old_tuple: Vec::new(),
+            new_tuple: to_column_values(new_tuple),
+        }),
+        Change::Update { relation_id, schema, table, old_tuple, new_tuple } => Event::Update(RowChange {
+            relation_id: *relation_id,
+            schema: schema.clone(),
+            table: table.clone(),
+            old_tuple: old_tuple.as_ref().map(to_column_values).unwrap_or_default(),
+            new_tuple: to_column_values(new_tuple),
+        }),
+        Change::Delete { relation_id, schema, table, old_tuple } => Event::Delete(RowChange {
+            relation_id: *relation_id,
+            schema: schema.clone(),
+            table: table.clone(),
+            old_tuple: to_column_values(old_tuple),
+            new_tuple: Vec::new(),
+        }),
+        // Streamed row changes carry the same shape as their regular
+        // counterparts; `RowChange` has no xid field, so the xid is dropped
+        // here (only the proto `Begin` message carries an xid).
+        Change::StreamInsert { relation_id, schema, table, new_tuple, .. } => Event::Insert(RowChange {
+            relation_id: *relation_id,
+            schema: schema.clone(),
+            table: table.clone(),
+            old_tuple: Vec::new(),
+            new_tuple: to_column_values(new_tuple),
+        }),
+        Change::StreamUpdate { relation_id, schema, table, old_tuple, new_tuple, .. } => Event::Update(RowChange {
+            relation_id: *relation_id,
+            schema: schema.clone(),
+            table: table.clone(),
+            old_tuple: old_tuple.as_ref().map(to_column_values).unwrap_or_default(),
+            new_tuple: to_column_values(new_tuple),
+        }),
+        Change::StreamDelete { relation_id, schema, table, old_tuple, .. } => Event::Delete(RowChange {
+            relation_id: *relation_id,
+            schema: schema.clone(),
+            table: table.clone(),
+            old_tuple: to_column_values(old_tuple),
+            new_tuple: Vec::new(),
+        }),
+        // Transaction/stream control messages and type-system metadata have
+        // no proto representation yet.
+        Change::StreamStart { .. }
+        | Change::StreamStop
+        | Change::StreamCommit { .. }
+        | Change::StreamAbort { .. }
+        | Change::Truncate { .. }
+        | Change::Origin { .. }
+        | Change::Type { .. }
+        | Change::LogicalMessage { .. }
+        | Change::BeginPrepare { .. }
+        | Change::Prepare { .. }
+        | Change::CommitPrepared { .. }
+        | Change::RollbackPrepared { .. }
+        | Change::StreamPrepare { .. } => return None,
+    };
+
+    Some(ChangeEvent { event: Some(event) })
+}
@@ -0,0 +1,40 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A PostgreSQL Log Sequence Number: a 64-bit WAL byte offset, displayed and
+/// parsed in Postgres's `"X/Y"` hex notation (the high and low 32-bit
+/// halves), replacing the ad hoc `format!("{:X}/{:X}", ...)` that used to be
+/// duplicated across the decoder and replication stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Lsn(pub u64);
+
+impl From<u64> for Lsn {
+    fn from(value: u64) -> Self {
+        Lsn(value)
+    }
+}
+
+impl From<Lsn> for u64 {
+    fn from(value: Lsn) -> Self {
+        value.0
+    }
+}
+
+impl FromStr for Lsn {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (high, low) = s.split_once('/').ok_or_else(|| anyhow!("Invalid LSN format: {}", s))?;
+        let high = u32::from_str_radix(high, 16)?;
+        let low = u32::from_str_radix(low, 16)?;
+        Ok(Lsn(((high as u64) << 32) | low as u64))
+    }
+}
+
+impl fmt::Display for Lsn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:X}/{:X}", self.0 >> 32, self.0 & 0xFFFF_FFFF)
+    }
+}
@@ -1,11 +1,29 @@
 mod replication;
 mod decoder;
 mod output;
+mod checkpoint;
+mod grpc_output;
+mod metrics;
+mod retry;
+mod dead_letter;
+mod filter;
+mod pg_type;
+mod lsn;
+mod replication_feedback;
+mod subject;
+mod schema;
+mod signed_output;
+mod durable_output;
 
 use clap::Parser;
 use anyhow::Result;
+use std::path::PathBuf;
 use std::sync::Arc;
+use checkpoint::CheckpointStore;
+use dead_letter::DeadLetterSink;
+use filter::ChangeFilter;
 use output::OutputTarget;
+use retry::RetryPolicy;
 
 #[derive(Parser, Debug)]
 #[command(name = "pgoutput-stream")]
@@ -23,10 +41,17 @@ struct Args {
     #[arg(short, long)]
     publication: String,
 
-    /// Output format: json, json-pretty, text, debezium, or feldera
+    /// Output format: json, json-pretty (or json-pretty:<indent-width>),
+    /// text, debezium, avro-schema, bigquery-schema, ndjson, or toml
     #[arg(short, long, default_value = "json")]
     format: String,
 
+    /// Disable OID-typed tuple values (native numbers/bools, base64 bytea)
+    /// for the stdout and NATS targets' JSON output, keeping the legacy
+    /// all-strings tuple shape instead.
+    #[arg(long)]
+    raw_text: bool,
+
     /// Create replication slot if it doesn't exist
     #[arg(long)]
     create_slot: bool,
@@ -35,10 +60,15 @@ struct Args {
     #[arg(long)]
     start_lsn: Option<String>,
 
-    /// Output target(s): stdout, nats, feldera (comma-separated for multiple)
+    /// Output target(s): stdout, nats, feldera, grpc, durable (comma-separated for multiple)
     #[arg(short, long, default_value = "stdout")]
     target: String,
 
+    /// Address for the gRPC change-stream server to listen on (required when
+    /// target includes 'grpc'), e.g. "0.0.0.0:50051"
+    #[arg(long)]
+    grpc_listen: Option<String>,
+
     /// NATS server URL (required when target includes 'nats')
     #[arg(long)]
     nats_server: Option<String>,
@@ -67,6 +97,51 @@ struct Args {
     /// Feldera API key for authentication (optional)
     #[arg(long)]
     feldera_api_key: Option<String>,
+
+    /// Directory for the durable LSN checkpoint store. When set and
+    /// --start-lsn is not given, streaming resumes from the last LSN every
+    /// output target durably accepted on a previous run.
+    #[arg(long)]
+    checkpoint_dir: Option<PathBuf>,
+
+    /// Directory for the durable output target's buffered-change store
+    /// (required when target includes 'durable')
+    #[arg(long)]
+    durable_dir: Option<PathBuf>,
+
+    /// Address for a Prometheus metrics endpoint, e.g. "0.0.0.0:9090"
+    #[arg(long)]
+    metrics_listen: Option<String>,
+
+    /// How often to sample replication lag for the metrics endpoint
+    #[arg(long, default_value = "15")]
+    metrics_sample_interval_secs: u64,
+
+    /// Maximum retry attempts per output target write before dead-lettering
+    #[arg(long, default_value = "3")]
+    max_retries: u32,
+
+    /// Base delay for the retry backoff (doubled on each attempt)
+    #[arg(long, default_value = "100")]
+    retry_base_delay_ms: u64,
+
+    /// JSONL file to append undeliverable changes and decode failures to,
+    /// instead of aborting the stream
+    #[arg(long)]
+    dead_letter_file: Option<PathBuf>,
+
+    /// Only forward changes for these tables (comma-separated, glob-style,
+    /// e.g. "public.users,analytics.*"). Applied before --exclude-tables.
+    #[arg(long)]
+    include_tables: Option<String>,
+
+    /// Never forward changes for these tables (comma-separated, glob-style)
+    #[arg(long)]
+    exclude_tables: Option<String>,
+
+    /// Only forward these operations (comma-separated: insert, update, delete)
+    #[arg(long)]
+    operations: Option<String>,
 }
 
 #[tokio::main]
@@ -78,30 +153,96 @@ async fn main() -> Result<()> {
     eprintln!("Publication: {}", args.publication);
     eprintln!("Output format: {}", args.format);
 
+    // If the `durable` target is requested, open it now - before `start_lsn`
+    // is resolved and before the replication stream is built - so its own
+    // resume position can be taken into account alongside `checkpoint_store`'s.
+    // It's reused as-is down in the `"durable" =>` target-construction arm
+    // below rather than reopened, since sled only allows one handle per
+    // process onto a given directory.
+    let target_list: Vec<&str> = args.target.split(',').map(|s| s.trim()).collect();
+    let durable_output = if target_list.contains(&"durable") {
+        let durable_dir = args
+            .durable_dir
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("--durable-dir is required when target includes 'durable'"))?;
+        Some(Arc::new(durable_output::DurableOutput::open(durable_dir)?))
+    } else {
+        None
+    };
+
+    // Open the checkpoint store (if configured) before connecting, so we can
+    // resume from the last durably-acknowledged LSN when --start-lsn isn't
+    // given explicitly.
+    let checkpoint_store = args
+        .checkpoint_dir
+        .as_ref()
+        .map(|dir| CheckpointStore::open(dir))
+        .transpose()?;
+
+    let start_lsn = match &args.start_lsn {
+        Some(lsn) => Some(lsn.clone()),
+        None => {
+            // checkpoint_store and the durable target's own checkpoint both
+            // claim to answer "where do we resume" - take whichever is
+            // further behind, so resuming never skips past data either sink
+            // hasn't durably accepted yet.
+            let checkpoint_resumed = checkpoint_store
+                .as_ref()
+                .map(|store| store.get(&args.slot))
+                .transpose()?
+                .flatten()
+                .map(|lsn| lsn.parse::<lsn::Lsn>())
+                .transpose()?;
+            let durable_resumed = durable_output.as_ref().map(|target| target.resume_from()).transpose()?.flatten();
+
+            let resumed = match (checkpoint_resumed, durable_resumed) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (Some(a), None) => Some(a),
+                (None, Some(b)) => Some(b),
+                (None, None) => None,
+            };
+
+            if let Some(lsn) = resumed {
+                eprintln!("Resuming slot '{}' from checkpoint: {}", args.slot, lsn);
+            }
+            resumed.map(|lsn| lsn.to_string())
+        }
+    };
+
+    let dead_letter = args
+        .dead_letter_file
+        .as_ref()
+        .map(|path| DeadLetterSink::open(path))
+        .transpose()?
+        .map(Arc::new);
+
     // Initialize replication stream
     let mut stream = replication::ReplicationStream::new(
         &args.connection,
         &args.slot,
         &args.publication,
         args.create_slot,
-        args.start_lsn,
+        start_lsn,
     )
     .await?;
+    if let Some(dead_letter) = &dead_letter {
+        stream = stream.with_dead_letter_sink(dead_letter.clone());
+    }
 
     eprintln!("Starting replication stream...\n");
 
     // Build output targets based on --target option
     let mut targets: Vec<Arc<dyn OutputTarget>> = Vec::new();
-    let target_list: Vec<&str> = args.target.split(',').map(|s| s.trim()).collect();
-    
+
     eprintln!("Output targets: {}", args.target);
     
     for target in target_list {
         match target {
             "stdout" => {
-                let stdout_output = output::StdoutOutput::new(output::OutputFormat::from_str(&args.format)?);
+                let stdout_output = output::StdoutOutput::new(output::OutputFormat::from_str(&args.format)?)
+                    .with_typed_json(!args.raw_text);
                 targets.push(Arc::new(stdout_output));
-                eprintln!("  - stdout (format: {})", args.format);
+                eprintln!("  - stdout (format: {}, raw-text: {})", args.format, args.raw_text);
             }
             "nats" => {
                 let nats_server = args.nats_server.as_ref()
@@ -116,7 +257,7 @@ async fn main() -> Result<()> {
                     nats_server,
                     &args.nats_stream,
                     args.nats_subject_prefix.clone(),
-                ).await?;
+                ).await?.with_typed_json(!args.raw_text);
                 targets.push(Arc::new(nats_output));
             }
             "feldera" => {
@@ -154,8 +295,30 @@ async fn main() -> Result<()> {
                 ).await?;
                 targets.push(Arc::new(feldera_output));
             }
+            "grpc" => {
+                let grpc_listen = args.grpc_listen.as_ref()
+                    .ok_or_else(|| anyhow::anyhow!("--grpc-listen is required when target includes 'grpc'"))?;
+
+                eprintln!("  - gRPC change stream:");
+                eprintln!("      Listening on: {}", grpc_listen);
+
+                let grpc_output = grpc_output::GrpcOutput::new(grpc_listen).await?;
+                targets.push(Arc::new(grpc_output));
+            }
+            "durable" => {
+                // Already opened above so its resume position could feed
+                // `start_lsn` before the replication stream was built.
+                let durable_output = durable_output.clone().expect("checked when resolving start_lsn");
+
+                eprintln!("  - Durable sled-backed store:");
+                eprintln!("      Directory: {}", args.durable_dir.as_ref().unwrap().display());
+                if let Some(resume_lsn) = durable_output.resume_from()? {
+                    eprintln!("      Last committed LSN: {}", resume_lsn);
+                }
+                targets.push(durable_output);
+            }
             _ => {
-                return Err(anyhow::anyhow!("Unknown target '{}'. Valid targets: stdout, nats, feldera", target));
+                return Err(anyhow::anyhow!("Unknown target '{}'. Valid targets: stdout, nats, feldera, grpc, durable", target));
             }
         }
     }
@@ -166,12 +329,52 @@ async fn main() -> Result<()> {
         return Err(anyhow::anyhow!("At least one output target must be specified"));
     }
     
+    let change_filter = ChangeFilter::new(
+        args.include_tables.as_deref(),
+        args.exclude_tables.as_deref(),
+        args.operations.as_deref(),
+    )?;
+
     // Create composite output
-    let output_handler = output::CompositeOutput::new(targets);
+    let retry_policy = RetryPolicy::new(
+        args.max_retries,
+        std::time::Duration::from_millis(args.retry_base_delay_ms),
+    );
+    let mut output_handler = output::CompositeOutput::new(targets).with_retry_policy(retry_policy);
+    if let Some(dead_letter) = &dead_letter {
+        output_handler = output_handler.with_dead_letter_sink(dead_letter.clone());
+    }
+
+    // Replay anything the durable store buffered but never saw acknowledged
+    // by a later commit - e.g. a previous run crashed mid-transaction -
+    // before normal streaming resumes, so a crash can't silently lose rows
+    // that were durably recorded but not yet delivered to every target.
+    if let Some(durable) = &durable_output {
+        let unacknowledged: Vec<decoder::Change> = durable.unacknowledged().collect::<Result<_, _>>()?;
+        if !unacknowledged.is_empty() {
+            eprintln!("Replaying {} buffered change(s) from the durable store...", unacknowledged.len());
+            for change in unacknowledged {
+                if let Err(e) = output_handler.write_change(&change, stream.decoder()).await {
+                    if e.downcast_ref::<output::DeadLettered>().is_none() {
+                        return Err(e);
+                    }
+                    eprintln!("buffered change dead-lettered after retries: {}", e);
+                }
+            }
+        }
+    }
+
+    if let Some(metrics_listen) = &args.metrics_listen {
+        eprintln!("Metrics endpoint listening on: {}", metrics_listen);
+        metrics::serve(metrics_listen).await?;
+    }
+    let mut metrics_sample_tick = tokio::time::interval(std::time::Duration::from_secs(
+        args.metrics_sample_interval_secs,
+    ));
 
     // Set up graceful shutdown
     let (shutdown_tx, mut shutdown_rx) = tokio::sync::mpsc::channel::<()>(1);
-    
+
     tokio::spawn(async move {
         tokio::signal::ctrl_c().await.ok();
         eprintln!("\nReceived shutdown signal, stopping...");
@@ -179,21 +382,70 @@ async fn main() -> Result<()> {
     });
 
     // Process replication stream
+    let mut pending_begin: Option<decoder::Change> = None;
+    let mut txn_has_visible_change = false;
+
     loop {
         tokio::select! {
             result = stream.next_message() => {
                 match result {
                     Ok(Some(change)) => {
-                        // Write change to output targets
-                        output_handler.write_change(&change).await?;
-                        
-                        // Mark LSN as processed for monitoring
-                        // Note: pg_logical_slot_get_binary_changes already auto-confirms,
-                        // this is for tracking/debugging purposes
-                        if let Some(lsn) = change.get_lsn() {
-                            stream.mark_processed(lsn);
-                        } else if let Some(lsn) = stream.last_received_lsn().map(|s| s.to_string()) {
-                            // For data events without LSN, use the last received LSN
+                        metrics::record_decoded(change.operation_name());
+
+                        // Begin markers are held back until a data change in the same
+                        // transaction actually passes the filter, so downstream
+                        // consumers never see empty transactions.
+                        if matches!(change, decoder::Change::Begin { .. }) {
+                            pending_begin = Some(change);
+                            txn_has_visible_change = false;
+                            continue;
+                        }
+
+                        if matches!(change, decoder::Change::Commit { .. }) && !txn_has_visible_change {
+                            pending_begin = None;
+                            continue;
+                        }
+
+                        if !change_filter.passes(&change) {
+                            continue;
+                        }
+
+                        if !matches!(change, decoder::Change::Commit { .. }) {
+                            txn_has_visible_change = true;
+                        }
+
+                        if let Some(begin) = pending_begin.take() {
+                            if let Err(e) = output_handler.write_change(&begin, stream.decoder()).await {
+                                if e.downcast_ref::<output::DeadLettered>().is_none() {
+                                    return Err(e);
+                                }
+                                eprintln!("begin marker dead-lettered after retries: {}", e);
+                            }
+                        }
+
+                        // Write change to output targets. A dead-lettered change was
+                        // recorded for later retry, not lost - but it also wasn't
+                        // durably accepted by every target, so we must not advance
+                        // the checkpoint for it; skip straight to the next message
+                        // instead of treating this like a fatal error.
+                        if let Err(e) = output_handler.write_change(&change, stream.decoder()).await {
+                            if e.downcast_ref::<output::DeadLettered>().is_none() {
+                                return Err(e);
+                            }
+                            eprintln!("change dead-lettered after retries, not advancing checkpoint: {}", e);
+                            continue;
+                        }
+
+                        // Only now that every output target has durably accepted the
+                        // change do we advance the flush LSN reported back to Postgres.
+                        let processed_lsn = change
+                            .get_lsn()
+                            .map(|s| s.to_string())
+                            .or_else(|| stream.last_received_lsn());
+                        if let Some(lsn) = processed_lsn {
+                            if let Some(store) = &checkpoint_store {
+                                store.set(&args.slot, &lsn)?;
+                            }
                             stream.mark_processed(&lsn);
                         }
                     }
@@ -222,6 +474,12 @@ async fn main() -> Result<()> {
                 }
                 break;
             }
+            _ = metrics_sample_tick.tick(), if args.metrics_listen.is_some() => {
+                match stream.lag_bytes().await {
+                    Ok(lag) => metrics::set_replication_lag_bytes(lag),
+                    Err(e) => eprintln!("Failed to sample replication lag: {}", e),
+                }
+            }
         }
     }
 
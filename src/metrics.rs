@@ -0,0 +1,99 @@
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use prometheus::{Encoder, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+
+static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+static CHANGES_DECODED_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    let counter = IntCounterVec::new(
+        Opts::new("pgoutput_changes_decoded_total", "Changes decoded, by operation type"),
+        &["operation"],
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+static WAL_BYTES_CONSUMED_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
+    let counter = IntCounter::new(
+        "pgoutput_wal_bytes_consumed_total",
+        "Bytes of WAL consumed from the replication stream",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+static TARGET_WRITE_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    let counter = IntCounterVec::new(
+        Opts::new("pgoutput_target_write_total", "Per-target write outcomes"),
+        &["target", "result"],
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+static REPLICATION_LAG_BYTES: Lazy<IntGauge> = Lazy::new(|| {
+    let gauge = IntGauge::new(
+        "pgoutput_replication_lag_bytes",
+        "pg_current_wal_lsn() minus confirmed_flush_lsn, in bytes",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
+pub fn record_decoded(operation: &str) {
+    CHANGES_DECODED_TOTAL.with_label_values(&[operation]).inc();
+}
+
+pub fn record_wal_bytes(bytes: u64) {
+    WAL_BYTES_CONSUMED_TOTAL.inc_by(bytes);
+}
+
+pub fn record_target_write(target: &str, success: bool) {
+    let result = if success { "success" } else { "failure" };
+    TARGET_WRITE_TOTAL.with_label_values(&[target, result]).inc();
+}
+
+pub fn set_replication_lag_bytes(lag: i64) {
+    REPLICATION_LAG_BYTES.set(lag);
+}
+
+/// Parse the `X/Y` hex LSN pair used throughout pgoutput into a plain u64
+/// byte offset, so lag can be computed with a simple subtraction.
+pub fn parse_lsn(lsn: &str) -> Result<u64> {
+    let (high, low) = lsn
+        .split_once('/')
+        .ok_or_else(|| anyhow::anyhow!("Invalid LSN format: {}", lsn))?;
+    let high = u32::from_str_radix(high, 16)?;
+    let low = u32::from_str_radix(low, 16)?;
+    Ok(((high as u64) << 32) | low as u64)
+}
+
+/// Serve Prometheus text-format metrics on `listen_addr` in the background.
+pub async fn serve(listen_addr: &str) -> Result<()> {
+    let addr: SocketAddr = listen_addr.parse()?;
+    let make_svc = make_service_fn(|_conn| async { Ok::<_, Infallible>(service_fn(handle_request)) });
+
+    tokio::spawn(async move {
+        if let Err(e) = Server::bind(&addr).serve(make_svc).await {
+            eprintln!("Metrics server error: {}", e);
+        }
+    });
+
+    Ok(())
+}
+
+async fn handle_request(_req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let metric_families = REGISTRY.gather();
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&metric_families, &mut buffer).unwrap();
+    Ok(Response::new(Body::from(buffer)))
+}
@@ -1,40 +1,237 @@
 use anyhow::{anyhow, Result};
-use crate::decoder::Change;
+use async_trait::async_trait;
+use crate::dead_letter::DeadLetterSink;
+use crate::decoder::{Change, Decoder};
+use crate::retry::RetryPolicy;
+use crate::schema::{AvroDialect, BigQueryDialect, SchemaEmitter};
+use crate::subject::SubjectBuilder;
+use serde::Serialize;
 use serde_json;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Clone)]
 pub enum OutputFormat {
     Json,
-    JsonPretty,
+    /// Pretty-printed JSON, indented by the given number of spaces per
+    /// nesting level. `json-pretty` (no suffix) defaults to 2; `json-pretty:4`
+    /// requests 4. `json-pretty:0` still prints one field per line, just
+    /// with no leading whitespace.
+    JsonPretty(usize),
     Text,
+    Debezium,
+    /// Prints an Avro record schema for each `Relation` seen, instead of row
+    /// data. Useful for auto-provisioning a downstream topic/table schema
+    /// from the replication stream itself.
+    AvroSchema,
+    /// Prints a BigQuery table schema for each `Relation` seen, instead of
+    /// row data.
+    BigQuerySchema,
+    /// One compact JSON object per line, each terminated by a newline - the
+    /// NDJSON convention streaming consumers parse by reading line-by-line.
+    /// Today this produces the same bytes as `Json`, but it's a distinct
+    /// variant so a consumer can depend on the one-object-per-line contract
+    /// itself rather than on `Json` incidentally satisfying it.
+    Ndjson,
+    /// Serializes each `Change` as a TOML document instead of JSON. TOML has
+    /// no `null`, so this errors on any change carrying a NULL column value;
+    /// pick a JSON format for tuples that might contain NULLs.
+    Toml,
 }
 
 impl OutputFormat {
     pub fn from_str(s: &str) -> Result<Self> {
-        match s.to_lowercase().as_str() {
+        let lower = s.to_lowercase();
+        if let Some(width) = lower.strip_prefix("json-pretty:") {
+            let indent = width
+                .parse::<usize>()
+                .map_err(|_| anyhow!("Invalid json-pretty indent width: {}", width))?;
+            return Ok(OutputFormat::JsonPretty(indent));
+        }
+
+        match lower.as_str() {
             "json" => Ok(OutputFormat::Json),
-            "json-pretty" => Ok(OutputFormat::JsonPretty),
+            "json-pretty" => Ok(OutputFormat::JsonPretty(2)),
             "text" => Ok(OutputFormat::Text),
-            _ => Err(anyhow!("Unknown output format: {}. Valid options: json, json-pretty, text", s)),
+            "debezium" => Ok(OutputFormat::Debezium),
+            "avro-schema" => Ok(OutputFormat::AvroSchema),
+            "bigquery-schema" => Ok(OutputFormat::BigQuerySchema),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            "toml" => Ok(OutputFormat::Toml),
+            _ => Err(anyhow!(
+                "Unknown output format: {}. Valid options: json, json-pretty (or json-pretty:<width>), text, debezium, avro-schema, bigquery-schema, ndjson, toml",
+                s
+            )),
         }
     }
 }
 
-pub fn print_change(change: &Change, format: &OutputFormat) -> Result<()> {
+/// The transaction a data change belongs to, carried forward from the
+/// enclosing `Begin` so it can be embedded in the Debezium envelope's
+/// `source` block. `None` for changes emitted before any `Begin` is seen.
+#[derive(Debug, Clone, Copy)]
+pub struct TxnContext<'a> {
+    pub lsn: &'a str,
+    pub xid: u32,
+}
+
+pub fn print_change(change: &Change, format: &OutputFormat, decoder: &Decoder) -> Result<()> {
+    print_change_with_txn(change, format, None, true, decoder)
+}
+
+/// `typed_json` selects between native JSON types (numbers, bools, a
+/// base64-encoded string for `bytea`) and the legacy all-strings shape for
+/// `OutputFormat::Json`/`JsonPretty`/`Ndjson`; pass `false` for `--raw-text`
+/// compatibility with consumers built against the original stringly-typed
+/// output. `decoder` must be the same `Decoder` that decoded `change` - see
+/// `Change::to_typed_json`.
+pub fn print_change_with_txn(
+    change: &Change,
+    format: &OutputFormat,
+    txn: Option<TxnContext>,
+    typed_json: bool,
+    decoder: &Decoder,
+) -> Result<()> {
     match format {
         OutputFormat::Json => {
-            println!("{}", serde_json::to_string(change)?);
+            if typed_json {
+                println!("{}", serde_json::to_string(&change.to_typed_json(decoder)?)?);
+            } else {
+                println!("{}", serde_json::to_string(change)?);
+            }
         }
-        OutputFormat::JsonPretty => {
-            println!("{}", serde_json::to_string_pretty(change)?);
+        OutputFormat::JsonPretty(indent) => {
+            let value = if typed_json { change.to_typed_json(decoder)? } else { serde_json::to_value(change)? };
+            println!("{}", to_pretty_json(&value, *indent)?);
         }
         OutputFormat::Text => {
             print_text_format(change);
         }
+        OutputFormat::Debezium => {
+            if let Some(envelope) = debezium_envelope(change, txn)? {
+                println!("{}", serde_json::to_string(&envelope)?);
+            }
+        }
+        OutputFormat::AvroSchema => {
+            if let Some(doc) = SchemaEmitter::new(AvroDialect).emit(change) {
+                println!("{}", serde_json::to_string_pretty(&doc)?);
+            }
+        }
+        OutputFormat::BigQuerySchema => {
+            if let Some(doc) = SchemaEmitter::new(BigQueryDialect).emit(change) {
+                println!("{}", serde_json::to_string_pretty(&doc)?);
+            }
+        }
+        OutputFormat::Ndjson => {
+            if typed_json {
+                println!("{}", serde_json::to_string(&change.to_typed_json(decoder)?)?);
+            } else {
+                println!("{}", serde_json::to_string(change)?);
+            }
+        }
+        OutputFormat::Toml => {
+            println!("{}", to_toml(change)?);
+        }
     }
     Ok(())
 }
 
+/// Render a `serde_json::Value` pretty-printed with the given per-level
+/// indent width, in spaces. `serde_json::to_string_pretty` hard-codes a
+/// 2-space indent; this goes through `PrettyFormatter::with_indent` instead
+/// so `OutputFormat::JsonPretty`'s configured width actually takes effect.
+pub fn to_pretty_json(value: &serde_json::Value, indent: usize) -> Result<String> {
+    let indent_bytes = vec![b' '; indent];
+    let mut buf = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(&indent_bytes);
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buf, formatter);
+    value.serialize(&mut serializer)?;
+    Ok(String::from_utf8(buf)?)
+}
+
+/// Render a `Change` as a TOML document. TOML has no `null`, so this errors
+/// on any change carrying a NULL column value.
+pub fn to_toml(change: &Change) -> Result<String> {
+    Ok(toml::to_string(change)?)
+}
+
+/// Build a Debezium-style change-event envelope for a data change. Returns
+/// `None` for Begin/Commit/Relation, which have no Debezium equivalent and
+/// are simply not emitted in this format.
+fn debezium_envelope(change: &Change, txn: Option<TxnContext>) -> Result<Option<serde_json::Value>> {
+    let (op, before, after, schema, table, row_xid) = match change {
+        Change::Insert { schema, table, new_tuple, .. } => {
+            ("c", None, Some(tuple_to_json(new_tuple)), schema, table, None)
+        }
+        Change::Update { schema, table, old_tuple, new_tuple, .. } => (
+            "u",
+            old_tuple.as_ref().map(tuple_to_json),
+            Some(tuple_to_json(new_tuple)),
+            schema,
+            table,
+            None,
+        ),
+        Change::Delete { schema, table, old_tuple, .. } => {
+            ("d", Some(tuple_to_json(old_tuple)), None, schema, table, None)
+        }
+        // Streamed changes carry their own xid, taking precedence over the
+        // enclosing (regular) transaction's xid since streamed transactions
+        // aren't bounded by a plain Begin/Commit pair.
+        Change::StreamInsert { schema, table, new_tuple, xid, .. } => {
+            ("c", None, Some(tuple_to_json(new_tuple)), schema, table, Some(*xid))
+        }
+        Change::StreamUpdate { schema, table, old_tuple, new_tuple, xid, .. } => (
+            "u",
+            old_tuple.as_ref().map(tuple_to_json),
+            Some(tuple_to_json(new_tuple)),
+            schema,
+            table,
+            Some(*xid),
+        ),
+        Change::StreamDelete { schema, table, old_tuple, xid, .. } => {
+            ("d", Some(tuple_to_json(old_tuple)), None, schema, table, Some(*xid))
+        }
+        // Transaction/stream control messages and type-system metadata have
+        // no Debezium row-change equivalent.
+        _ => return Ok(None),
+    };
+
+    let source = serde_json::json!({
+        "connector": "pgoutput-stream",
+        "db": "postgres",
+        "schema": schema,
+        "table": table,
+        "lsn": txn.map(|t| t.lsn),
+        "txId": row_xid.or(txn.map(|t| t.xid)),
+    });
+
+    Ok(Some(serde_json::json!({
+        "payload": {
+            "before": before,
+            "after": after,
+            "op": op,
+            "ts_ms": now_ms(),
+            "source": source,
+        }
+    })))
+}
+
+fn tuple_to_json(tuple: &indexmap::IndexMap<String, Option<String>>) -> serde_json::Value {
+    serde_json::Value::Object(
+        tuple
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone().map(serde_json::Value::String).unwrap_or(serde_json::Value::Null)))
+            .collect(),
+    )
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
 fn print_text_format(change: &Change) {
     match change {
         Change::Begin { lsn, timestamp, xid } => {
@@ -89,5 +286,445 @@ fn print_text_format(change: &Change) {
                 }
             }
         }
+        Change::StreamStart { xid, first_segment } => {
+            println!("STREAM START [XID: {}, First segment: {}]", xid, first_segment);
+        }
+        Change::StreamStop => {
+            println!("STREAM STOP");
+        }
+        Change::StreamCommit { xid, commit_lsn, end_lsn, timestamp } => {
+            println!(
+                "STREAM COMMIT [XID: {}, LSN: {}, End LSN: {}, Time: {}]",
+                xid, commit_lsn, end_lsn, timestamp
+            );
+        }
+        Change::StreamAbort { xid, subxid } => {
+            println!("STREAM ABORT [XID: {}, Subxid: {}]", xid, subxid);
+        }
+        Change::Truncate { relations, cascade, restart_identity } => {
+            println!(
+                "TRUNCATE {:?} [Cascade: {}, Restart identity: {}]",
+                relations, cascade, restart_identity
+            );
+        }
+        Change::Origin { commit_lsn, name } => {
+            println!("ORIGIN [LSN: {}, Name: {}]", commit_lsn, name);
+        }
+        Change::Type { type_id, namespace, name } => {
+            println!("TYPE [ID: {}, {}.{}]", type_id, namespace, name);
+        }
+        Change::LogicalMessage { transactional, lsn, prefix, content } => {
+            println!(
+                "MESSAGE [LSN: {}, Prefix: {}, Transactional: {}, {} bytes]",
+                lsn, prefix, transactional, content.len()
+            );
+        }
+        Change::StreamInsert { xid, relation_id, schema, table, new_tuple } => {
+            println!("STREAM INSERT into {}.{} (ID: {}, XID: {})", schema, table, relation_id, xid);
+            println!("  New values:");
+            for (key, value) in new_tuple {
+                match value {
+                    Some(v) => println!("    {}: {}", key, v),
+                    None => println!("    {}: NULL", key),
+                }
+            }
+        }
+        Change::StreamUpdate { xid, relation_id, schema, table, old_tuple, new_tuple } => {
+            println!("STREAM UPDATE {}.{} (ID: {}, XID: {})", schema, table, relation_id, xid);
+            if let Some(old) = old_tuple {
+                println!("  Old values:");
+                for (key, value) in old {
+                    match value {
+                        Some(v) => println!("    {}: {}", key, v),
+                        None => println!("    {}: NULL", key),
+                    }
+                }
+            }
+            println!("  New values:");
+            for (key, value) in new_tuple {
+                match value {
+                    Some(v) => println!("    {}: {}", key, v),
+                    None => println!("    {}: NULL", key),
+                }
+            }
+        }
+        Change::StreamDelete { xid, relation_id, schema, table, old_tuple } => {
+            println!("STREAM DELETE from {}.{} (ID: {}, XID: {})", schema, table, relation_id, xid);
+            println!("  Old values:");
+            for (key, value) in old_tuple {
+                match value {
+                    Some(v) => println!("    {}: {}", key, v),
+                    None => println!("    {}: NULL", key),
+                }
+            }
+        }
+        Change::BeginPrepare { prepare_lsn, end_lsn, prepare_timestamp, xid, gid } => {
+            println!(
+                "BEGIN PREPARE [GID: {}, XID: {}, LSN: {}, End LSN: {}, Time: {}]",
+                gid, xid, prepare_lsn, end_lsn, prepare_timestamp
+            );
+        }
+        Change::Prepare { prepare_lsn, end_lsn, prepare_timestamp, xid, gid } => {
+            println!(
+                "PREPARE [GID: {}, XID: {}, LSN: {}, End LSN: {}, Time: {}]",
+                gid, xid, prepare_lsn, end_lsn, prepare_timestamp
+            );
+        }
+        Change::CommitPrepared { commit_lsn, end_lsn, commit_timestamp, xid, gid } => {
+            println!(
+                "COMMIT PREPARED [GID: {}, XID: {}, LSN: {}, End LSN: {}, Time: {}]",
+                gid, xid, commit_lsn, end_lsn, commit_timestamp
+            );
+        }
+        Change::RollbackPrepared { prepare_end_lsn, rollback_end_lsn, prepare_timestamp, rollback_timestamp, xid, gid } => {
+            println!(
+                "ROLLBACK PREPARED [GID: {}, XID: {}, Prepare LSN: {}, Rollback LSN: {}, Prepare time: {}, Rollback time: {}]",
+                gid, xid, prepare_end_lsn, rollback_end_lsn, prepare_timestamp, rollback_timestamp
+            );
+        }
+        Change::StreamPrepare { prepare_lsn, end_lsn, prepare_timestamp, xid, gid } => {
+            println!(
+                "STREAM PREPARE [GID: {}, XID: {}, LSN: {}, End LSN: {}, Time: {}]",
+                gid, xid, prepare_lsn, end_lsn, prepare_timestamp
+            );
+        }
+    }
+}
+
+/// A destination that decoded `Change` events are written to.
+///
+/// Implementations must be safe to share across the replication loop and any
+/// number of concurrent writers, since `CompositeOutput` fans a single change
+/// out to every configured target.
+#[async_trait]
+pub trait OutputTarget: Send + Sync {
+    /// Write a single decoded change. Returning `Err` means the change was
+    /// *not* durably accepted by this target; callers must not advance the
+    /// confirmed LSN for a target that errors.
+    ///
+    /// `decoder` is the `Decoder` that actually decoded `change`, so a
+    /// target that renders typed JSON (see `Change::to_typed_json`) resolves
+    /// columns against the relation cache that's actually populated, instead
+    /// of an unrelated default.
+    async fn write_change(&self, change: &Change, decoder: &Decoder) -> Result<()>;
+
+    /// Short identifier used in logs and dead-letter records (e.g. "stdout").
+    fn name(&self) -> &str;
+}
+
+/// Writes changes to stdout in the configured `OutputFormat`.
+///
+/// For `OutputFormat::Debezium`, tracks the enclosing transaction's LSN and
+/// xid from the last `Begin` seen, so each data change's envelope carries an
+/// accurate `source.lsn`/`source.txId` without threading that state through
+/// `Change` itself.
+pub struct StdoutOutput {
+    format: OutputFormat,
+    current_txn: Mutex<Option<(String, u32)>>,
+    /// When true (the default), `Json`/`JsonPretty` tuples are printed with
+    /// their PostgreSQL-OID-derived JSON type (number/bool/base64 bytea/...)
+    /// instead of as quoted strings. Disable for `--raw-text` compatibility
+    /// with the legacy all-strings shape.
+    typed_json: bool,
+}
+
+impl StdoutOutput {
+    pub fn new(format: OutputFormat) -> Self {
+        Self { format, current_txn: Mutex::new(None), typed_json: true }
+    }
+
+    /// Opt out of OID-typed JSON tuples and print the legacy all-strings
+    /// shape instead (`--raw-text`).
+    pub fn with_typed_json(mut self, typed_json: bool) -> Self {
+        self.typed_json = typed_json;
+        self
+    }
+}
+
+#[async_trait]
+impl OutputTarget for StdoutOutput {
+    async fn write_change(&self, change: &Change, decoder: &Decoder) -> Result<()> {
+        if let Change::Begin { lsn, xid, .. } = change {
+            *self.current_txn.lock().unwrap() = Some((lsn.clone(), *xid));
+        }
+
+        let current_txn = self.current_txn.lock().unwrap();
+        let txn = current_txn.as_ref().map(|(lsn, xid)| TxnContext { lsn, xid: *xid });
+        print_change_with_txn(change, &self.format, txn, self.typed_json, decoder)
+    }
+
+    fn name(&self) -> &str {
+        "stdout"
+    }
+}
+
+/// Publishes changes to a NATS JetStream stream, one subject per
+/// schema/table/operation (e.g. `postgres.public.users.insert`).
+pub struct NatsOutput {
+    jetstream: async_nats::jetstream::Context,
+    subject_builder: SubjectBuilder,
+    /// When true (the default), tuple columns are published with their
+    /// PostgreSQL-OID-derived JSON type (number/bool/null/...) instead of
+    /// as quoted strings. Disable for consumers that depend on the legacy
+    /// all-strings tuple shape.
+    typed_json: bool,
+}
+
+impl NatsOutput {
+    pub async fn new(server: &str, stream_name: &str, subject_prefix: String) -> Result<Self> {
+        let client = async_nats::connect(server).await?;
+        let jetstream = async_nats::jetstream::new(client);
+
+        jetstream
+            .get_or_create_stream(async_nats::jetstream::stream::Config {
+                name: stream_name.to_string(),
+                subjects: vec![format!("{}.>", subject_prefix)],
+                ..Default::default()
+            })
+            .await?;
+
+        Ok(Self { jetstream, subject_builder: SubjectBuilder::new(subject_prefix), typed_json: true })
+    }
+
+    /// Opt out of OID-typed JSON tuples and publish the legacy all-strings
+    /// shape instead.
+    pub fn with_typed_json(mut self, typed_json: bool) -> Self {
+        self.typed_json = typed_json;
+        self
+    }
+
+    /// Override the default `{prefix}.{schema}.{table}.{operation}` subject
+    /// template. See `SubjectBuilder` for the placeholders available.
+    pub fn with_subject_template(mut self, template: impl Into<String>) -> Self {
+        self.subject_builder = self.subject_builder.with_template(template);
+        self
+    }
+}
+
+#[async_trait]
+impl OutputTarget for NatsOutput {
+    async fn write_change(&self, change: &Change, decoder: &Decoder) -> Result<()> {
+        let subjects = change.subjects(&self.subject_builder);
+        if subjects.is_empty() {
+            return Err(anyhow!("no NATS subject for change: {:?}", change));
+        }
+        let payload = if self.typed_json {
+            serde_json::to_vec(&change.to_typed_json(decoder)?)?
+        } else {
+            serde_json::to_vec(change)?
+        };
+        for subject in subjects {
+            self.jetstream.publish(subject, payload.clone().into()).await?.await?;
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "nats"
+    }
+}
+
+/// Pushes changes into a Feldera pipeline over its HTTP ingress endpoint,
+/// routing each change to a `{schema}_{table}` input table.
+pub struct FelderaOutput {
+    http: reqwest::Client,
+    base_url: String,
+    pipeline: String,
+    allowed_tables: Option<Vec<String>>,
+    api_key: Option<String>,
+}
+
+impl FelderaOutput {
+    pub async fn new(
+        base_url: &str,
+        pipeline: &str,
+        allowed_tables: Option<Vec<String>>,
+        api_key: Option<&str>,
+    ) -> Result<Self> {
+        Ok(Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            pipeline: pipeline.to_string(),
+            allowed_tables,
+            api_key: api_key.map(|k| k.to_string()),
+        })
+    }
+
+    fn table_name(schema: &str, table: &str) -> String {
+        format!("{}_{}", schema, table)
+    }
+
+    fn is_allowed(&self, table_name: &str) -> bool {
+        match &self.allowed_tables {
+            Some(tables) => tables.iter().any(|t| t == table_name),
+            None => true,
+        }
+    }
+}
+
+#[async_trait]
+impl OutputTarget for FelderaOutput {
+    async fn write_change(&self, change: &Change, _decoder: &Decoder) -> Result<()> {
+        let (table_name, body) = match change {
+            Change::Insert { schema, table, new_tuple, .. } => {
+                let name = Self::table_name(schema, table);
+                (name, serde_json::json!({"insert": new_tuple}))
+            }
+            Change::Update { schema, table, new_tuple, .. } => {
+                let name = Self::table_name(schema, table);
+                (name, serde_json::json!({"update": new_tuple}))
+            }
+            Change::Delete { schema, table, old_tuple, .. } => {
+                let name = Self::table_name(schema, table);
+                (name, serde_json::json!({"delete": old_tuple}))
+            }
+            Change::StreamInsert { schema, table, new_tuple, .. } => {
+                let name = Self::table_name(schema, table);
+                (name, serde_json::json!({"insert": new_tuple}))
+            }
+            Change::StreamUpdate { schema, table, new_tuple, .. } => {
+                let name = Self::table_name(schema, table);
+                (name, serde_json::json!({"update": new_tuple}))
+            }
+            Change::StreamDelete { schema, table, old_tuple, .. } => {
+                let name = Self::table_name(schema, table);
+                (name, serde_json::json!({"delete": old_tuple}))
+            }
+            // Transaction/stream control messages and type-system metadata
+            // have no Feldera table to route to.
+            _ => return Ok(()),
+        };
+
+        if !self.is_allowed(&table_name) {
+            return Ok(());
+        }
+
+        let url = format!(
+            "{}/v0/pipelines/{}/ingress/{}?format=json&update_format=insert_delete",
+            self.base_url, self.pipeline, table_name
+        );
+
+        let mut request = self.http.post(&url).json(&body);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Feldera ingress for {} returned {}",
+                table_name,
+                response.status()
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "feldera"
+    }
+}
+
+/// Returned by `CompositeOutput::write_change` when every failing target was
+/// successfully dead-lettered rather than lost outright - distinct from a
+/// generic failure so callers (the replication main loop) can tell "this
+/// change was recorded for retry later, move on without advancing the
+/// checkpoint" apart from "this change is gone, the process must stop".
+#[derive(Debug)]
+pub struct DeadLettered {
+    pub targets: Vec<String>,
+}
+
+impl std::fmt::Display for DeadLettered {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "output target(s) [{}] failed after retries and were dead-lettered, not durably written",
+            self.targets.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for DeadLettered {}
+
+/// Fans a single change out to every configured target.
+///
+/// A write is only considered successful once *all* targets have accepted
+/// it, so the caller can safely advance the confirmed LSN only after
+/// `write_change` returns `Ok`. Each target's write is retried with
+/// exponential backoff per `retry_policy`; if retries are exhausted and a
+/// `dead_letter` sink is configured, the change is recorded there, the
+/// remaining targets are still written, and `write_change` returns a
+/// `DeadLettered` error (rather than a plain failure) once all have been tried.
+pub struct CompositeOutput {
+    targets: Vec<Arc<dyn OutputTarget>>,
+    retry_policy: RetryPolicy,
+    dead_letter: Option<Arc<DeadLetterSink>>,
+}
+
+impl CompositeOutput {
+    pub fn new(targets: Vec<Arc<dyn OutputTarget>>) -> Self {
+        Self { targets, retry_policy: RetryPolicy::none(), dead_letter: None }
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn with_dead_letter_sink(mut self, dead_letter: Arc<DeadLetterSink>) -> Self {
+        self.dead_letter = Some(dead_letter);
+        self
+    }
+}
+
+#[async_trait]
+impl OutputTarget for CompositeOutput {
+    async fn write_change(&self, change: &Change, decoder: &Decoder) -> Result<()> {
+        // A dead-lettered target never durably accepted the change, so per
+        // the `OutputTarget::write_change` contract this still has to surface
+        // as an overall `Err` once every target's been tried - dead-lettering
+        // means "we didn't lose it", not "every target has it", and the
+        // caller must not advance the confirmed LSN on the strength of a
+        // dead-letter record alone.
+        let mut dead_lettered_targets: Vec<String> = Vec::new();
+
+        for target in &self.targets {
+            let result = self
+                .retry_policy
+                .run(target.name(), || target.write_change(change, decoder))
+                .await;
+
+            match result {
+                Ok(()) => crate::metrics::record_target_write(target.name(), true),
+                Err(e) => {
+                    crate::metrics::record_target_write(target.name(), false);
+
+                    if let Some(dead_letter) = &self.dead_letter {
+                        dead_letter.record_write_failure(target.name(), change.get_lsn(), change, &e)?;
+                        eprintln!(
+                            "output target '{}' failed after retries, dead-lettered: {}",
+                            target.name(),
+                            e
+                        );
+                        dead_lettered_targets.push(target.name().to_string());
+                        continue;
+                    }
+
+                    return Err(anyhow!("output target '{}' failed: {}", target.name(), e));
+                }
+            }
+        }
+
+        if !dead_lettered_targets.is_empty() {
+            return Err(DeadLettered { targets: dead_lettered_targets }.into());
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "composite"
     }
 }
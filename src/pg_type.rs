@@ -0,0 +1,53 @@
+use phf::phf_map;
+
+/// A PostgreSQL scalar type family that the decoder knows how to parse a
+/// column's text representation into, resolved from the column's `type_id`
+/// (OID) carried on the enclosing RELATION message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PgType {
+    Bool,
+    Int2,
+    Int4,
+    Int8,
+    Float4,
+    Float8,
+    Numeric,
+    Text,
+    Bytea,
+    Uuid,
+    Json,
+    Date,
+    Timestamp,
+    Timestamptz,
+}
+
+/// Well-known PostgreSQL type OIDs, in the spirit of rust-postgres's
+/// `phf`-generated SQLSTATE map: a compile-time perfect-hash table so column
+/// type lookups are a const-time array probe instead of a runtime `HashMap`
+/// built on every decode.
+static PG_TYPE_BY_OID: phf::Map<u32, PgType> = phf_map! {
+    16u32 => PgType::Bool,
+    20u32 => PgType::Int8,
+    21u32 => PgType::Int2,
+    23u32 => PgType::Int4,
+    700u32 => PgType::Float4,
+    701u32 => PgType::Float8,
+    1700u32 => PgType::Numeric,
+    25u32 => PgType::Text,
+    1043u32 => PgType::Text,
+    17u32 => PgType::Bytea,
+    2950u32 => PgType::Uuid,
+    114u32 => PgType::Json,
+    3802u32 => PgType::Json,
+    1082u32 => PgType::Date,
+    1114u32 => PgType::Timestamp,
+    1184u32 => PgType::Timestamptz,
+};
+
+/// Look up the `PgType` for a column's `type_id`, if it's one of the
+/// well-known OIDs this decoder understands. Unmapped OIDs return `None` so
+/// the caller can fall back to `Value::Unknown` and stay forward-compatible
+/// with types this table hasn't caught up with yet.
+pub fn lookup(type_id: u32) -> Option<PgType> {
+    PG_TYPE_BY_OID.get(&type_id).copied()
+}
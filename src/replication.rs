@@ -1,17 +1,47 @@
-use anyhow::Result;
-use tokio_postgres::{Client, NoTls, SimpleQueryMessage};
-use std::time::Duration;
+use anyhow::{anyhow, Result};
+use bytes::{Buf, Bytes};
+use futures_util::{SinkExt, StreamExt};
 use std::collections::VecDeque;
+use std::pin::Pin;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio_postgres::replication::LogicalReplicationStream;
+use tokio_postgres::{Client, NoTls, SimpleQueryMessage};
+
+use crate::dead_letter::DeadLetterSink;
+use crate::decoder::{Change, Decoder};
+use crate::lsn::Lsn;
+use crate::metrics;
+use crate::replication_feedback::{encode_standby_status_update, parse_primary_keepalive};
+use std::sync::Arc;
+
+/// Microsecond offset between the Unix epoch and the Postgres epoch
+/// (2000-01-01 00:00:00 UTC), used to translate replication protocol
+/// timestamps into/out of wall-clock time.
+const PG_EPOCH_OFFSET_MICROS: i64 = 946_684_800_000_000;
 
-use crate::decoder::{decode_pgoutput_message, Change};
+/// How often a standby status update is sent even if the server hasn't
+/// asked for one, to keep `confirmed_flush_lsn` moving and the connection
+/// alive.
+const STANDBY_STATUS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// `proto_version` negotiated with `START_REPLICATION`. Version 2 adds the
+/// streamed-transaction messages (Stream Start/Stop/Commit/Abort and the
+/// xid-prefixed row/relation messages inside them).
+const PROTOCOL_VERSION: u8 = 2;
 
 pub struct ReplicationStream {
-    client: Client,
+    /// Plain connection used for monitoring queries (slot status, slot
+    /// creation); the replication connection is busy running COPY BOTH and
+    /// can't be used for ad-hoc SQL while streaming.
+    status_client: Client,
     slot_name: String,
-    publication_name: String,
+    copy_stream: Pin<Box<LogicalReplicationStream>>,
+    decoder: Decoder,
     change_buffer: VecDeque<Change>,
-    last_received_lsn: Option<String>,
-    last_processed_lsn: Option<String>,
+    last_received_lsn: Option<u64>,
+    last_flushed_lsn: Option<u64>,
+    next_status_update: tokio::time::Instant,
+    dead_letter: Option<Arc<DeadLetterSink>>,
 }
 
 impl ReplicationStream {
@@ -20,23 +50,19 @@ impl ReplicationStream {
         slot_name: &str,
         publication_name: &str,
         create_slot: bool,
-        _start_lsn: Option<String>,
+        start_lsn: Option<String>,
     ) -> Result<Self> {
-        // Parse connection string
-        let config = connection_string.parse::<tokio_postgres::Config>()?;
-
-        // Create a client
-        let (client, connection) = config.connect(NoTls).await?;
-
+        // A regular connection for slot management and status queries.
+        let status_config = connection_string.parse::<tokio_postgres::Config>()?;
+        let (status_client, status_connection) = status_config.connect(NoTls).await?;
         tokio::spawn(async move {
-            if let Err(e) = connection.await {
-                eprintln!("Connection error: {}", e);
+            if let Err(e) = status_connection.await {
+                eprintln!("Status connection error: {}", e);
             }
         });
 
-        // Create replication slot if requested
         if create_slot {
-            match Self::create_replication_slot(&client, slot_name).await {
+            match Self::create_replication_slot(&status_client, slot_name).await {
                 Ok(_) => eprintln!("Created replication slot: {}", slot_name),
                 Err(e) => {
                     let err_msg = e.to_string().to_lowercase();
@@ -49,108 +75,194 @@ impl ReplicationStream {
             }
         }
 
+        // A dedicated connection in replication mode to run START_REPLICATION.
+        let mut replication_config = connection_string.parse::<tokio_postgres::Config>()?;
+        replication_config.replication_mode(tokio_postgres::config::ReplicationMode::Logical);
+        let (replication_client, replication_connection) = replication_config.connect(NoTls).await?;
+        tokio::spawn(async move {
+            if let Err(e) = replication_connection.await {
+                eprintln!("Replication connection error: {}", e);
+            }
+        });
+
+        let start_lsn = start_lsn.unwrap_or_else(|| "0/0".to_string());
+        let query = format!(
+            "START_REPLICATION SLOT {} LOGICAL {} (proto_version '{}', publication_names '{}')",
+            quote_identifier(slot_name),
+            start_lsn,
+            PROTOCOL_VERSION,
+            publication_name
+        );
+
+        let copy_stream = replication_client
+            .copy_both_simple::<Bytes>(&query)
+            .await?;
+        let copy_stream = Box::pin(LogicalReplicationStream::new(copy_stream));
+
+        let mut decoder = Decoder::new();
+        decoder.set_protocol_version(PROTOCOL_VERSION);
+
         eprintln!("Starting replication stream...\n");
 
         Ok(Self {
-            client,
+            status_client,
             slot_name: slot_name.to_string(),
-            publication_name: publication_name.to_string(),
+            copy_stream,
+            decoder,
             change_buffer: VecDeque::new(),
-            last_received_lsn: None,
-            last_processed_lsn: None,
+            last_received_lsn: parse_lsn(&start_lsn).ok(),
+            last_flushed_lsn: None,
+            next_status_update: tokio::time::Instant::now() + STANDBY_STATUS_INTERVAL,
+            dead_letter: None,
         })
     }
 
+    /// Route malformed pgoutput buffers to `dead_letter` instead of aborting
+    /// the stream on the next decode failure.
+    pub fn with_dead_letter_sink(mut self, dead_letter: Arc<DeadLetterSink>) -> Self {
+        self.dead_letter = Some(dead_letter);
+        self
+    }
+
     async fn create_replication_slot(client: &Client, slot_name: &str) -> Result<()> {
         // Use SQL function instead of replication protocol command
         let query = format!(
             "SELECT pg_create_logical_replication_slot('{}', 'pgoutput')",
             slot_name
         );
-        
+
         let rows = client.simple_query(&query).await?;
-        
+
         for row in rows {
             if let SimpleQueryMessage::Row(row) = row {
                 eprintln!("Slot created: {:?}", row);
             }
         }
-        
+
         Ok(())
     }
 
     pub async fn next_message(&mut self) -> Result<Option<Change>> {
-        // If we have buffered changes, return the next one
         if let Some(change) = self.change_buffer.pop_front() {
             return Ok(Some(change));
         }
 
-        // Poll for changes and buffer them
         loop {
-            let query = format!(
-                "SELECT lsn, xid, data FROM pg_logical_slot_get_binary_changes('{}', NULL, NULL, 'proto_version', '1', 'publication_names', '{}')",
-                self.slot_name, self.publication_name
-            );
-
-            let rows = self.client.query(&query, &[]).await?;
-            
-            if rows.is_empty() {
-                // No changes available, sleep briefly and retry
-                tokio::time::sleep(Duration::from_millis(100)).await;
+            if tokio::time::Instant::now() >= self.next_status_update {
+                self.send_standby_status_update(false).await?;
+            }
+
+            let Some(message) = self.copy_stream.next().await else {
+                return Ok(None);
+            };
+            let mut data = message?;
+
+            if data.is_empty() {
                 continue;
             }
 
-            // Process all rows and buffer the changes
-            for row in rows {
-                let lsn: String = row.get(0);
-                let data: Vec<u8> = row.get(2);
-                
-                // Update last received LSN
-                self.last_received_lsn = Some(lsn.clone());
-                
-                // Decode the pgoutput message
-                if let Some(change) = decode_pgoutput_message(&data)? {
-                    self.change_buffer.push_back(change);
+            match data.get_u8() {
+                b'w' => {
+                    // XLogData: start_lsn(8) + wal_end(8) + timestamp(8) + payload
+                    if data.len() < 24 {
+                        return Err(anyhow!("Invalid XLogData message"));
+                    }
+                    let _start_lsn = data.get_u64();
+                    let wal_end = data.get_u64();
+                    let _server_time = data.get_i64();
+                    self.last_received_lsn = Some(wal_end);
+                    metrics::record_wal_bytes(data.len() as u64);
+
+                    match self.decoder.decode_message(&data) {
+                        Ok(Some(change)) => self.change_buffer.push_back(change),
+                        Ok(None) => {}
+                        Err(e) => match &self.dead_letter {
+                            Some(dead_letter) => {
+                                dead_letter.record_decode_failure(&data, &e)?;
+                                eprintln!("Failed to decode pgoutput message, dead-lettered: {}", e);
+                            }
+                            None => return Err(e),
+                        },
+                    }
+
+                    if let Some(change) = self.change_buffer.pop_front() {
+                        return Ok(Some(change));
+                    }
                 }
-            }
+                b'k' => {
+                    let keepalive = parse_primary_keepalive(data)?;
+                    self.last_received_lsn = Some(keepalive.wal_end.into());
 
-            // Return the first buffered change
-            if let Some(change) = self.change_buffer.pop_front() {
-                return Ok(Some(change));
+                    if keepalive.reply_requested {
+                        self.send_standby_status_update(false).await?;
+                    }
+                }
+                other => {
+                    eprintln!("Unknown CopyData message type: {}", other as char);
+                }
             }
         }
     }
-    
-    /// Mark an LSN as successfully processed
-    /// Note: pg_logical_slot_get_binary_changes already auto-confirms,
-    /// but this tracks progress for monitoring/debugging
+
+    async fn send_standby_status_update(&mut self, reply: bool) -> Result<()> {
+        let write_lsn = Lsn::from(self.last_received_lsn.unwrap_or(0));
+        let flush_lsn = Lsn::from(self.last_flushed_lsn.unwrap_or(0));
+        let apply_lsn = flush_lsn;
+        let client_time = now_in_pg_epoch_micros();
+
+        let message = encode_standby_status_update(write_lsn, flush_lsn, apply_lsn, client_time, reply);
+
+        self.copy_stream.send(message).await?;
+        self.next_status_update = tokio::time::Instant::now() + STANDBY_STATUS_INTERVAL;
+        Ok(())
+    }
+
+    /// Record that `lsn` has been durably written to every configured output
+    /// target. This is what advances the flush LSN reported to Postgres on
+    /// the next standby status update, so a target failure never silently
+    /// loses WAL: the slot simply isn't told to discard it.
     pub fn mark_processed(&mut self, lsn: &str) {
-        self.last_processed_lsn = Some(lsn.to_string());
+        match parse_lsn(lsn) {
+            Ok(value) => {
+                if self.last_flushed_lsn.map_or(true, |current| value > current) {
+                    self.last_flushed_lsn = Some(value);
+                }
+            }
+            Err(e) => eprintln!("Failed to parse LSN '{}': {}", lsn, e),
+        }
     }
-    
+
     /// Get the last received LSN from PostgreSQL
-    pub fn last_received_lsn(&self) -> Option<&str> {
-        self.last_received_lsn.as_deref()
+    pub fn last_received_lsn(&self) -> Option<String> {
+        self.last_received_lsn.map(format_lsn)
     }
-    
-    /// Get the last successfully processed LSN
-    pub fn last_processed_lsn(&self) -> Option<&str> {
-        self.last_processed_lsn.as_deref()
+
+    /// Get the last successfully processed (flushed) LSN
+    pub fn last_processed_lsn(&self) -> Option<String> {
+        self.last_flushed_lsn.map(format_lsn)
+    }
+
+    /// The `Decoder` this stream decodes messages through - callers rendering
+    /// a `Change` from this stream (e.g. `Change::to_typed_json`) must resolve
+    /// columns/custom-types against this same `Decoder`, not a separate one,
+    /// since relation and custom-type caches are learned per-stream.
+    pub fn decoder(&self) -> &Decoder {
+        &self.decoder
     }
-    
+
     /// Get replication slot status from PostgreSQL
     pub async fn get_slot_status(&self) -> Result<SlotStatus> {
         let query = format!(
             "SELECT confirmed_flush_lsn, restart_lsn, active FROM pg_replication_slots WHERE slot_name = '{}'",
             self.slot_name
         );
-        
-        let rows = self.client.query(&query, &[]).await?;
-        
+
+        let rows = self.status_client.query(&query, &[]).await?;
+
         if rows.is_empty() {
             return Err(anyhow::anyhow!("Replication slot '{}' not found", self.slot_name));
         }
-        
+
         let row = &rows[0];
         Ok(SlotStatus {
             confirmed_flush_lsn: row.get(0),
@@ -158,6 +270,46 @@ impl ReplicationStream {
             active: row.get(2),
         })
     }
+
+    /// Sample replication lag in bytes: `pg_current_wal_lsn() -
+    /// confirmed_flush_lsn`. Used to drive the `pgoutput_replication_lag_bytes`
+    /// gauge so operators can alert when the consumer falls behind.
+    pub async fn lag_bytes(&self) -> Result<i64> {
+        let status = self.get_slot_status().await?;
+        let confirmed = parse_lsn(&status.confirmed_flush_lsn)?;
+
+        let rows = self
+            .status_client
+            .query("SELECT pg_current_wal_lsn()::text", &[])
+            .await?;
+        let current_wal_lsn: String = rows
+            .first()
+            .ok_or_else(|| anyhow!("pg_current_wal_lsn() returned no rows"))?
+            .get(0);
+        let current = parse_lsn(&current_wal_lsn)?;
+
+        Ok(current as i64 - confirmed as i64)
+    }
+}
+
+fn quote_identifier(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+fn parse_lsn(lsn: &str) -> Result<u64> {
+    lsn.parse::<Lsn>().map(u64::from)
+}
+
+fn format_lsn(lsn: u64) -> String {
+    Lsn::from(lsn).to_string()
+}
+
+fn now_in_pg_epoch_micros() -> i64 {
+    let unix_micros = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros() as i64;
+    unix_micros - PG_EPOCH_OFFSET_MICROS
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,52 @@
+use anyhow::{anyhow, Result};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use crate::lsn::Lsn;
+
+/// Size in bytes of a Standby Status Update ('r') message, including its
+/// leading type byte.
+const STANDBY_STATUS_UPDATE_LEN: usize = 1 + 8 + 8 + 8 + 8 + 1;
+
+/// Encode a Standby Status Update (`'r'`) message: the client->server
+/// feedback that reports write/flush/apply progress and asks (or not) for
+/// an immediate keepalive reply. This is the write side needed to actually
+/// advance a replication slot's `confirmed_flush_lsn`.
+pub fn encode_standby_status_update(
+    write_lsn: Lsn,
+    flush_lsn: Lsn,
+    apply_lsn: Lsn,
+    client_time: i64,
+    reply_requested: bool,
+) -> Bytes {
+    let mut buf = BytesMut::with_capacity(STANDBY_STATUS_UPDATE_LEN);
+    buf.put_u8(b'r');
+    buf.put_u64(write_lsn.into());
+    buf.put_u64(flush_lsn.into());
+    buf.put_u64(apply_lsn.into());
+    buf.put_i64(client_time);
+    buf.put_u8(if reply_requested { 1 } else { 0 });
+    buf.freeze()
+}
+
+/// A parsed Primary Keepalive (`'k'`) message: the server asking the
+/// standby to confirm it's still alive, and optionally reply immediately.
+#[derive(Debug, Clone, Copy)]
+pub struct PrimaryKeepalive {
+    pub wal_end: Lsn,
+    pub server_time: i64,
+    pub reply_requested: bool,
+}
+
+/// Parse a Primary Keepalive (`'k'`) message body; the caller is expected
+/// to have already stripped the leading message-type byte.
+pub fn parse_primary_keepalive(mut data: impl Buf) -> Result<PrimaryKeepalive> {
+    if data.remaining() < 17 {
+        return Err(anyhow!("Invalid keepalive message"));
+    }
+
+    let wal_end = Lsn::from(data.get_u64());
+    let server_time = data.get_i64();
+    let reply_requested = data.get_u8() != 0;
+
+    Ok(PrimaryKeepalive { wal_end, server_time, reply_requested })
+}
@@ -0,0 +1,52 @@
+use anyhow::Result;
+use std::future::Future;
+use std::time::Duration;
+
+/// Exponential-backoff retry policy shared by every `OutputTarget` write.
+///
+/// `base_delay` is doubled on each attempt (`base_delay * 2^attempt`), so
+/// `max_retries = 3, base_delay = 100ms` sleeps 100ms, 200ms, then 400ms
+/// before giving up.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32, base_delay: Duration) -> Self {
+        Self { max_retries, base_delay }
+    }
+
+    /// No retries, no delay - useful as a default when retry isn't configured.
+    pub fn none() -> Self {
+        Self { max_retries: 0, base_delay: Duration::ZERO }
+    }
+
+    pub async fn run<F, Fut, T>(&self, label: &str, mut attempt_fn: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match attempt_fn().await {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < self.max_retries => {
+                    let delay = self.base_delay * 2u32.pow(attempt);
+                    eprintln!(
+                        "{}: attempt {}/{} failed ({}), retrying in {:?}",
+                        label,
+                        attempt + 1,
+                        self.max_retries,
+                        e,
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::decoder::{Change, ColumnInfo};
+use crate::pg_type::{self, PgType};
+
+/// Bit in `ColumnInfo::flags` marking a column as part of the relation's
+/// replica identity (its "key"), per the pgoutput RELATION message format.
+const KEY_COLUMN_FLAG: u8 = 0x01;
+
+fn is_key_column(column: &ColumnInfo) -> bool {
+    column.flags & KEY_COLUMN_FLAG != 0
+}
+
+/// Produces a target system's schema document for one relation's columns.
+/// Implemented per destination (Avro, BigQuery, ...) so `SchemaEmitter`
+/// itself stays destination-agnostic.
+pub trait SchemaDialect {
+    fn emit(&self, schema: &str, table: &str, columns: &[ColumnInfo]) -> serde_json::Value;
+}
+
+/// Emits an Avro record schema. Key columns are required; every other
+/// column is wrapped in a `["null", T]` union so missing/NULL values
+/// round-trip.
+pub struct AvroDialect;
+
+impl SchemaDialect for AvroDialect {
+    fn emit(&self, schema: &str, table: &str, columns: &[ColumnInfo]) -> serde_json::Value {
+        let fields: Vec<serde_json::Value> = columns
+            .iter()
+            .map(|column| {
+                let avro_type = avro_type_for(column.type_id);
+                let field_type = if is_key_column(column) {
+                    avro_type
+                } else {
+                    serde_json::json!(["null", avro_type])
+                };
+                serde_json::json!({ "name": column.name, "type": field_type })
+            })
+            .collect();
+
+        serde_json::json!({
+            "type": "record",
+            "name": table,
+            "namespace": schema,
+            "fields": fields,
+        })
+    }
+}
+
+fn avro_type_for(type_id: u32) -> serde_json::Value {
+    match pg_type::lookup(type_id) {
+        Some(PgType::Int2) | Some(PgType::Int4) => serde_json::json!("int"),
+        Some(PgType::Int8) => serde_json::json!("long"),
+        Some(PgType::Bool) => serde_json::json!("boolean"),
+        Some(PgType::Float4) => serde_json::json!("float"),
+        Some(PgType::Float8) => serde_json::json!("double"),
+        Some(PgType::Bytea) => serde_json::json!("bytes"),
+        // Avro has no native decimal precision/scale signal from pgoutput
+        // alone (the RELATION message's type_modifier isn't carried on
+        // `ColumnInfo`), so NUMERIC columns use generous defaults.
+        Some(PgType::Numeric) => serde_json::json!({ "type": "bytes", "logicalType": "decimal", "precision": 38, "scale": 9 }),
+        Some(PgType::Timestamp) | Some(PgType::Timestamptz) => {
+            serde_json::json!({ "type": "long", "logicalType": "timestamp-micros" })
+        }
+        Some(PgType::Text) | Some(PgType::Uuid) | Some(PgType::Json) | Some(PgType::Date) => serde_json::json!("string"),
+        None => serde_json::json!("string"),
+    }
+}
+
+/// Emits a BigQuery table schema (the `schema.fields` shape accepted by the
+/// `tables.insert`/`tables.update` APIs). Key columns are `REQUIRED`; every
+/// other column is `NULLABLE`.
+pub struct BigQueryDialect;
+
+impl SchemaDialect for BigQueryDialect {
+    fn emit(&self, schema: &str, table: &str, columns: &[ColumnInfo]) -> serde_json::Value {
+        let fields: Vec<serde_json::Value> = columns
+            .iter()
+            .map(|column| {
+                serde_json::json!({
+                    "name": column.name,
+                    "type": bigquery_type_for(column.type_id),
+                    "mode": if is_key_column(column) { "REQUIRED" } else { "NULLABLE" },
+                })
+            })
+            .collect();
+
+        serde_json::json!({
+            "tableReference": { "datasetId": schema, "tableId": table },
+            "schema": { "fields": fields },
+        })
+    }
+}
+
+fn bigquery_type_for(type_id: u32) -> &'static str {
+    match pg_type::lookup(type_id) {
+        Some(PgType::Int2) | Some(PgType::Int4) | Some(PgType::Int8) => "INT64",
+        Some(PgType::Bool) => "BOOL",
+        Some(PgType::Float4) | Some(PgType::Float8) => "FLOAT64",
+        Some(PgType::Numeric) => "NUMERIC",
+        Some(PgType::Timestamp) | Some(PgType::Timestamptz) => "TIMESTAMP",
+        Some(PgType::Date) => "DATE",
+        Some(PgType::Bytea) => "BYTES",
+        Some(PgType::Uuid) | Some(PgType::Text) | Some(PgType::Json) | None => "STRING",
+    }
+}
+
+/// Turns `Change::Relation` events into a target schema document via a
+/// pluggable `SchemaDialect`, so a downstream sink (a BigQuery table, an
+/// Avro topic schema, ...) can be auto-provisioned straight from the
+/// replication stream's own table metadata.
+pub struct SchemaEmitter<D: SchemaDialect> {
+    dialect: D,
+}
+
+impl<D: SchemaDialect> SchemaEmitter<D> {
+    pub fn new(dialect: D) -> Self {
+        Self { dialect }
+    }
+
+    /// Render the schema document for a `Change::Relation`. Returns `None`
+    /// for every other variant, since only RELATION events carry column
+    /// metadata.
+    pub fn emit(&self, change: &Change) -> Option<serde_json::Value> {
+        match change {
+            Change::Relation { schema, table, columns, .. } => Some(self.dialect.emit(schema, table, columns)),
+            _ => None,
+        }
+    }
+}
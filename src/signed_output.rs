@@ -0,0 +1,217 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, Mutex};
+
+use crate::decoder::{Change, Decoder};
+use crate::output::{OutputTarget, TxnContext};
+
+/// A signing key plus the algorithm/`kid` it signs with. RS256 keys are
+/// loaded from an RSA PEM; ES256 keys from an EC PEM (both converted to DER
+/// internally by `jsonwebtoken`, which only signs DER-encoded keys).
+pub struct SigningKey {
+    algorithm: Algorithm,
+    kid: String,
+    encoding_key: EncodingKey,
+}
+
+impl SigningKey {
+    pub fn from_rsa_pem(pem: &[u8], kid: impl Into<String>) -> Result<Self> {
+        Ok(Self {
+            algorithm: Algorithm::RS256,
+            kid: kid.into(),
+            encoding_key: EncodingKey::from_rsa_pem(pem)?,
+        })
+    }
+
+    pub fn from_ec_pem(pem: &[u8], kid: impl Into<String>) -> Result<Self> {
+        Ok(Self {
+            algorithm: Algorithm::ES256,
+            kid: kid.into(),
+            encoding_key: EncodingKey::from_ec_pem(pem)?,
+        })
+    }
+}
+
+/// The counterpart to `SigningKey`, used by `verify_envelope` to check a
+/// compact JWS produced by `sign_change`.
+pub struct VerifyingKey {
+    algorithm: Algorithm,
+    decoding_key: DecodingKey,
+}
+
+impl VerifyingKey {
+    pub fn from_rsa_pem(pem: &[u8]) -> Result<Self> {
+        Ok(Self { algorithm: Algorithm::RS256, decoding_key: DecodingKey::from_rsa_pem(pem)? })
+    }
+
+    pub fn from_ec_pem(pem: &[u8]) -> Result<Self> {
+        Ok(Self { algorithm: Algorithm::ES256, decoding_key: DecodingKey::from_ec_pem(pem)? })
+    }
+}
+
+/// The payload embedded in every signed envelope: the change itself plus
+/// the commit context a verifier needs without re-joining the original
+/// stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedPayload {
+    pub change: Change,
+    pub lsn: Option<String>,
+    pub xid: Option<u32>,
+}
+
+/// A verified envelope: the inner change plus the chain hash carried in its
+/// header, so a caller can confirm it against the previous envelope it
+/// verified.
+#[derive(Debug, Clone)]
+pub struct VerifiedEnvelope {
+    pub payload: SignedPayload,
+    pub kid: String,
+    /// sha256 hex digest of the previous envelope's signature segment, or
+    /// `None` if this was the first envelope in its chain.
+    pub prev_signature_hash: Option<String>,
+}
+
+/// Sign `payload` into a compact JWS (`base64url(header).base64url(payload).base64url(signature)`),
+/// chaining it to `prev_signature` by hashing that signature (if any) into
+/// the header's `psh` field so a verifier walking the stream can detect
+/// gaps or reordering.
+pub fn sign_change(payload: &SignedPayload, key: &SigningKey, prev_signature: Option<&str>) -> Result<String> {
+    let header = serde_json::json!({
+        "alg": algorithm_name(key.algorithm),
+        "kid": key.kid,
+        "psh": prev_signature.map(hash_signature),
+    });
+
+    let header_b64 = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header)?);
+    let payload_b64 = URL_SAFE_NO_PAD.encode(serde_json::to_vec(payload)?);
+    let signing_input = format!("{}.{}", header_b64, payload_b64);
+
+    let signature_b64 = jsonwebtoken::crypto::sign(signing_input.as_bytes(), &key.encoding_key, key.algorithm)?;
+
+    Ok(format!("{}.{}", signing_input, signature_b64))
+}
+
+/// Verify a compact JWS produced by `sign_change` and return its payload and
+/// chain metadata. Returns an error if the signature doesn't verify, which
+/// covers both a wrong key and a tampered header/payload.
+pub fn verify_envelope(jws: &str, key: &VerifyingKey) -> Result<VerifiedEnvelope> {
+    let mut parts = jws.splitn(3, '.');
+    let (header_b64, payload_b64, signature_b64) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(h), Some(p), Some(s)) => (h, p, s),
+        _ => return Err(anyhow!("malformed JWS: expected 3 dot-separated segments")),
+    };
+
+    let signing_input = format!("{}.{}", header_b64, payload_b64);
+    let verified = jsonwebtoken::crypto::verify(signature_b64, signing_input.as_bytes(), &key.decoding_key, key.algorithm)?;
+    if !verified {
+        return Err(anyhow!("JWS signature does not verify"));
+    }
+
+    let header: serde_json::Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(header_b64)?)?;
+    let kid = header
+        .get("kid")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow!("JWS header missing kid"))?
+        .to_string();
+    let prev_signature_hash = header.get("psh").and_then(|v| v.as_str()).map(|s| s.to_string());
+
+    let payload: SignedPayload = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload_b64)?)?;
+
+    Ok(VerifiedEnvelope { payload, kid, prev_signature_hash })
+}
+
+fn hash_signature(signature_b64: &str) -> String {
+    let digest = Sha256::digest(signature_b64.as_bytes());
+    hex::encode(digest)
+}
+
+fn algorithm_name(algorithm: Algorithm) -> &'static str {
+    match algorithm {
+        Algorithm::RS256 => "RS256",
+        Algorithm::ES256 => "ES256",
+        _ => "RS256",
+    }
+}
+
+/// Decorates an `OutputTarget` with a parallel, independently-verifiable
+/// JWS audit trail. The wrapped target still receives every change
+/// unchanged (so existing delivery/format behavior is untouched); the
+/// signed compact JWS for each change is additionally appended to an
+/// in-memory chain, retrievable via `signed_envelopes()` for publishing to
+/// wherever downstream verification tooling reads from (a file, a separate
+/// topic, the dead-letter store, ...).
+///
+/// None of today's `OutputTarget` implementations expose a raw-bytes write
+/// path (they each serialize `&Change` to their own wire format
+/// internally), so there's no way for a decorator to substitute the signed
+/// JWS *for* the normal payload without changing every sink; layering the
+/// signed trail alongside normal delivery avoids that blast radius while
+/// still giving verifiers a tamper-evident, order-checkable record.
+pub struct SignedOutput {
+    inner: Arc<dyn OutputTarget>,
+    key: SigningKey,
+    /// The enclosing transaction's LSN/xid, tracked from the last `Begin`
+    /// seen, mirroring `StdoutOutput`'s approach to giving every row change
+    /// an accurate commit context without threading it through `Change`.
+    current_txn: Mutex<Option<(String, u32)>>,
+    last_signature: Mutex<Option<String>>,
+    signed_envelopes: Mutex<Vec<String>>,
+}
+
+impl SignedOutput {
+    pub fn new(inner: Arc<dyn OutputTarget>, key: SigningKey) -> Self {
+        Self {
+            inner,
+            key,
+            current_txn: Mutex::new(None),
+            last_signature: Mutex::new(None),
+            signed_envelopes: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The compact JWS for every change signed so far, oldest first.
+    pub fn signed_envelopes(&self) -> Vec<String> {
+        self.signed_envelopes.lock().unwrap().clone()
+    }
+
+    fn sign(&self, change: &Change, txn: Option<TxnContext>) -> Result<String> {
+        let payload = SignedPayload {
+            change: change.clone(),
+            lsn: txn.map(|t| t.lsn.to_string()),
+            xid: txn.map(|t| t.xid),
+        };
+
+        let mut last_signature = self.last_signature.lock().unwrap();
+        let jws = sign_change(&payload, &self.key, last_signature.as_deref())?;
+
+        let signature = jws.rsplit('.').next().unwrap_or_default().to_string();
+        *last_signature = Some(signature);
+
+        Ok(jws)
+    }
+}
+
+#[async_trait]
+impl OutputTarget for SignedOutput {
+    async fn write_change(&self, change: &Change, decoder: &Decoder) -> Result<()> {
+        if let Change::Begin { lsn, xid, .. } = change {
+            *self.current_txn.lock().unwrap() = Some((lsn.clone(), *xid));
+        }
+
+        let current_txn = self.current_txn.lock().unwrap();
+        let txn = current_txn.as_ref().map(|(lsn, xid)| TxnContext { lsn, xid: *xid });
+        let jws = self.sign(change, txn)?;
+        drop(current_txn);
+        self.signed_envelopes.lock().unwrap().push(jws);
+
+        self.inner.write_change(change, decoder).await
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+}
@@ -0,0 +1,63 @@
+use crate::decoder::Change;
+
+/// Template used when a `SubjectBuilder` isn't configured with its own:
+/// `{prefix}.{schema}.{table}.{operation}`, matching the NATS subject scheme
+/// this crate has used since its first output target.
+const DEFAULT_TEMPLATE: &str = "{prefix}.{schema}.{table}.{operation}";
+
+/// Builds NATS subjects from a configurable template, substituting
+/// `{prefix}`, `{schema}`, `{table}`, `{operation}` and `{xid}` placeholders
+/// with the values a `Change` carries (or the `"transactions"`/`"system"`
+/// placeholders this crate has always used for changes without a
+/// schema/table, such as `Begin`/`Commit`).
+///
+/// Every substituted token other than `{prefix}` is sanitized by replacing
+/// `.`, space, `*` and `>` with `_`, since those are structurally meaningful
+/// in NATS subjects (`.` separates tokens, `*`/`>` are wildcards) and a raw
+/// schema/table name containing one could otherwise silently break routing
+/// or subscribe to more than intended. `{prefix}` is left untouched since
+/// it's operator-configured and is expected to already contain the `.`
+/// separators that start the subject.
+pub struct SubjectBuilder {
+    prefix: String,
+    template: String,
+}
+
+impl SubjectBuilder {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self { prefix: prefix.into(), template: DEFAULT_TEMPLATE.to_string() }
+    }
+
+    pub fn with_template(mut self, template: impl Into<String>) -> Self {
+        self.template = template.into();
+        self
+    }
+
+    pub fn build(&self, change: &Change) -> String {
+        let (schema, table, operation, xid) = change.subject_parts();
+        self.build_parts(&schema, &table, &operation, xid)
+    }
+
+    /// Substitute an explicit `(schema, table, operation, xid)` instead of
+    /// deriving them from a `Change` - used for multi-table statements like
+    /// `TRUNCATE a, b, c`, which need one subject per table rather than the
+    /// single subject `subject_parts()` yields.
+    pub(crate) fn build_parts(&self, schema: &str, table: &str, operation: &str, xid: u32) -> String {
+        self.template
+            .replace("{prefix}", &self.prefix)
+            .replace("{schema}", &sanitize(schema))
+            .replace("{table}", &sanitize(table))
+            .replace("{operation}", &sanitize(operation))
+            .replace("{xid}", &xid.to_string())
+    }
+}
+
+fn sanitize(token: &str) -> String {
+    token
+        .chars()
+        .map(|c| match c {
+            '.' | ' ' | '*' | '>' => '_',
+            other => other,
+        })
+        .collect()
+}
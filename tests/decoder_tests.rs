@@ -403,13 +403,13 @@ fn test_get_lsn_from_commit() {
 /// Tests that data events (Insert, Update, Delete) return None for LSN
 #[test]
 fn test_get_lsn_from_data_events() {
-    use std::collections::HashMap;
+    use indexmap::IndexMap;
     
     let insert = Change::Insert {
         relation_id: 100,
         schema: "public".to_string(),
         table: "test".to_string(),
-        new_tuple: HashMap::new(),
+        new_tuple: IndexMap::new(),
     };
     assert_eq!(insert.get_lsn(), None);
     
@@ -418,7 +418,7 @@ fn test_get_lsn_from_data_events() {
         schema: "public".to_string(),
         table: "test".to_string(),
         old_tuple: None,
-        new_tuple: HashMap::new(),
+        new_tuple: IndexMap::new(),
     };
     assert_eq!(update.get_lsn(), None);
     
@@ -426,7 +426,7 @@ fn test_get_lsn_from_data_events() {
         relation_id: 100,
         schema: "public".to_string(),
         table: "test".to_string(),
-        old_tuple: HashMap::new(),
+        old_tuple: IndexMap::new(),
     };
     assert_eq!(delete.get_lsn(), None);
 }
@@ -440,6 +440,183 @@ fn test_get_lsn_from_relation() {
         table: "test".to_string(),
         columns: vec![],
     };
-    
+
     assert_eq!(change.get_lsn(), None);
 }
+
+/// Tests that a decoded tuple's JSON key order matches the column order
+/// declared by the preceding RELATION message, not insertion/hash order.
+/// Regression test for the switch from `HashMap` to `IndexMap` tuple fields.
+#[test]
+fn test_insert_tuple_preserves_relation_column_order() {
+    // Register a relation with columns deliberately out of alphabetical
+    // order, so a HashMap's nondeterministic order (or an alphabetically
+    // sorted map) would fail this test but an order-preserving map won't.
+    let mut relation_data = vec![b'R'];
+    relation_data.extend_from_slice(&200u32.to_be_bytes());
+    relation_data.extend_from_slice(b"public\0");
+    relation_data.extend_from_slice(b"ordered\0");
+    relation_data.push(1);
+    relation_data.extend_from_slice(&3u16.to_be_bytes());
+    relation_data.push(1);
+    relation_data.extend_from_slice(b"zebra\0");
+    relation_data.extend_from_slice(&23u32.to_be_bytes());
+    relation_data.extend_from_slice(&(-1i32).to_be_bytes());
+    relation_data.push(0);
+    relation_data.extend_from_slice(b"mango\0");
+    relation_data.extend_from_slice(&1043u32.to_be_bytes());
+    relation_data.extend_from_slice(&(-1i32).to_be_bytes());
+    relation_data.push(0);
+    relation_data.extend_from_slice(b"apple\0");
+    relation_data.extend_from_slice(&1043u32.to_be_bytes());
+    relation_data.extend_from_slice(&(-1i32).to_be_bytes());
+
+    decode_pgoutput_message(&relation_data).unwrap();
+
+    let mut data = vec![b'I'];
+    data.extend_from_slice(&200u32.to_be_bytes());
+    data.push(b'N');
+    data.extend_from_slice(&3u16.to_be_bytes());
+    for value in ["1", "2", "3"] {
+        data.push(b't');
+        data.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        data.extend_from_slice(value.as_bytes());
+    }
+
+    let result = decode_pgoutput_message(&data).unwrap();
+
+    match result {
+        Some(Change::Insert { new_tuple, .. }) => {
+            let keys: Vec<&String> = new_tuple.keys().collect();
+            assert_eq!(keys, vec!["zebra", "mango", "apple"]);
+
+            let json = serde_json::to_string(&new_tuple).unwrap();
+            let zebra_pos = json.find("zebra").unwrap();
+            let mango_pos = json.find("mango").unwrap();
+            let apple_pos = json.find("apple").unwrap();
+            assert!(zebra_pos < mango_pos && mango_pos < apple_pos);
+        }
+        _ => panic!("Expected Insert change"),
+    }
+}
+
+/// Tests that `Change::to_typed_json` renders columns with their
+/// PostgreSQL-OID-derived JSON type (native numbers/bools, base64 bytea)
+/// instead of the quoted strings a plain `serde_json::to_string` would
+/// produce, and that a NULL column stays JSON `null` either way.
+#[test]
+fn test_to_typed_json_renders_native_types_and_base64_bytea() {
+    // bool, int4, bytea, and a NULL column, in that order.
+    let mut relation_data = vec![b'R'];
+    relation_data.extend_from_slice(&300u32.to_be_bytes());
+    relation_data.extend_from_slice(b"public\0");
+    relation_data.extend_from_slice(b"typed\0");
+    relation_data.push(1);
+    relation_data.extend_from_slice(&4u16.to_be_bytes());
+    relation_data.push(1);
+    relation_data.extend_from_slice(b"active\0");
+    relation_data.extend_from_slice(&16u32.to_be_bytes()); // bool
+    relation_data.extend_from_slice(&(-1i32).to_be_bytes());
+    relation_data.push(0);
+    relation_data.extend_from_slice(b"count\0");
+    relation_data.extend_from_slice(&23u32.to_be_bytes()); // int4
+    relation_data.extend_from_slice(&(-1i32).to_be_bytes());
+    relation_data.push(0);
+    relation_data.extend_from_slice(b"payload\0");
+    relation_data.extend_from_slice(&17u32.to_be_bytes()); // bytea
+    relation_data.extend_from_slice(&(-1i32).to_be_bytes());
+    relation_data.push(0);
+    relation_data.extend_from_slice(b"note\0");
+    relation_data.extend_from_slice(&1043u32.to_be_bytes()); // text
+    relation_data.extend_from_slice(&(-1i32).to_be_bytes());
+
+    let mut decoder = Decoder::new();
+    decoder.decode_message(&relation_data).unwrap();
+
+    let mut data = vec![b'I'];
+    data.extend_from_slice(&300u32.to_be_bytes());
+    data.push(b'N');
+    data.extend_from_slice(&4u16.to_be_bytes());
+
+    data.push(b't');
+    data.extend_from_slice(&1u32.to_be_bytes());
+    data.extend_from_slice(b"t");
+
+    data.push(b't');
+    data.extend_from_slice(&2u32.to_be_bytes());
+    data.extend_from_slice(b"42");
+
+    data.push(b't');
+    data.extend_from_slice(&10u32.to_be_bytes());
+    data.extend_from_slice(b"\\xdeadbeef");
+
+    data.push(b'n'); // NULL
+
+    let change = decoder.decode_message(&data).unwrap().expect("Expected Insert change");
+    let typed = change.to_typed_json(&decoder).unwrap();
+    let new_tuple = &typed["Insert"]["new_tuple"];
+
+    assert_eq!(new_tuple["active"], serde_json::json!(true));
+    assert_eq!(new_tuple["count"], serde_json::json!(42));
+    assert_eq!(new_tuple["payload"], serde_json::json!("3q2+7w=="));
+    assert_eq!(new_tuple["note"], serde_json::Value::Null);
+}
+
+/// `Value::Bytea`'s `to_json` specifically: base64, not Postgres's own
+/// `\x`-hex text encoding, so binary payloads round-trip through JSON.
+#[test]
+fn test_bytea_value_to_json_is_base64() {
+    let decoder = Decoder::new();
+    let value = typed_tuple(&decoder, 999, &{
+        let mut tuple = indexmap::IndexMap::new();
+        tuple.insert("payload".to_string(), Some("\\xdeadbeef".to_string()));
+        tuple
+    });
+
+    // No RELATION was ever registered for relation_id 999, so the column's
+    // type is unknown and the raw text is preserved verbatim...
+    assert_eq!(value["payload"], Value::Unknown("\\xdeadbeef".to_string()));
+
+    // ...but feeding the same text through `parse_typed_value`'s `Bytea` arm
+    // (as happens once a RELATION has taught the decoder the column's OID)
+    // decodes it to raw bytes and renders those as base64, not hex.
+    let decoded = Value::Bytea(vec![0xde, 0xad, 0xbe, 0xef]);
+    assert_eq!(decoded.to_json(), serde_json::json!("3q2+7w=="));
+}
+
+/// A column whose OID isn't in `pg_type`'s well-known table but was named by
+/// an earlier `Type` message (the common case: a user-defined enum) should
+/// resolve to `Value::Custom` instead of falling all the way through to
+/// `Value::Unknown` - and its `to_json` should still read as a plain string,
+/// the same as any other text-like column.
+#[test]
+fn test_custom_type_resolves_via_preceding_type_message() {
+    let mut type_data = vec![b'Y'];
+    type_data.extend_from_slice(&50000u32.to_be_bytes());
+    type_data.extend_from_slice(b"public\0");
+    type_data.extend_from_slice(b"mood\0");
+    let mut decoder = Decoder::new();
+    decoder.decode_message(&type_data).unwrap();
+
+    let mut relation_data = vec![b'R'];
+    relation_data.extend_from_slice(&777u32.to_be_bytes());
+    relation_data.extend_from_slice(b"public\0");
+    relation_data.extend_from_slice(b"people\0");
+    relation_data.push(1);
+    relation_data.extend_from_slice(&1u16.to_be_bytes());
+    relation_data.push(1);
+    relation_data.extend_from_slice(b"current_mood\0");
+    relation_data.extend_from_slice(&50000u32.to_be_bytes());
+    relation_data.extend_from_slice(&(-1i32).to_be_bytes());
+    decoder.decode_message(&relation_data).unwrap();
+
+    let mut tuple = indexmap::IndexMap::new();
+    tuple.insert("current_mood".to_string(), Some("happy".to_string()));
+    let typed = typed_tuple(&decoder, 777, &tuple);
+
+    assert_eq!(
+        typed["current_mood"],
+        Value::Custom { type_name: "public.mood".to_string(), value: "happy".to_string() }
+    );
+    assert_eq!(typed["current_mood"].to_json(), serde_json::json!("happy"));
+}
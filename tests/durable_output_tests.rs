@@ -0,0 +1,139 @@
+use indexmap::IndexMap;
+use pgoutput_cmdline::decoder::{Change, Decoder};
+use pgoutput_cmdline::durable_output::DurableOutput;
+use pgoutput_cmdline::output::OutputTarget;
+use std::path::PathBuf;
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pgoutput_durable_output_test_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn begin(lsn: &str, xid: u32) -> Change {
+    Change::Begin { lsn: lsn.to_string(), timestamp: 0, xid }
+}
+
+fn commit(lsn: &str) -> Change {
+    Change::Commit { lsn: lsn.to_string(), timestamp: 0 }
+}
+
+fn insert(id: &str) -> Change {
+    let mut new_tuple = IndexMap::new();
+    new_tuple.insert("id".to_string(), Some(id.to_string()));
+    Change::Insert { relation_id: 1, schema: "public".to_string(), table: "users".to_string(), new_tuple }
+}
+
+fn stream_start(xid: u32) -> Change {
+    Change::StreamStart { xid, first_segment: true }
+}
+
+fn stream_insert(xid: u32, id: &str) -> Change {
+    let mut new_tuple = IndexMap::new();
+    new_tuple.insert("id".to_string(), Some(id.to_string()));
+    Change::StreamInsert { xid, relation_id: 1, schema: "public".to_string(), table: "users".to_string(), new_tuple }
+}
+
+fn stream_commit(xid: u32, commit_lsn: &str) -> Change {
+    Change::StreamCommit { xid, commit_lsn: commit_lsn.to_string(), end_lsn: commit_lsn.to_string(), timestamp: 0 }
+}
+
+#[tokio::test]
+async fn test_uncommitted_changes_survive_reopen() {
+    let dir = test_dir("uncommitted");
+    let decoder = Decoder::new();
+
+    {
+        let target = DurableOutput::open(&dir).unwrap();
+        target.write_change(&begin("0/10", 1), &decoder).await.unwrap();
+        target.write_change(&insert("1"), &decoder).await.unwrap();
+        // No commit: simulates a crash mid-transaction.
+    }
+
+    let reopened = DurableOutput::open(&dir).unwrap();
+    assert_eq!(reopened.resume_from().unwrap(), None);
+
+    let buffered: Vec<Change> = reopened.unacknowledged().collect::<Result<_, _>>().unwrap();
+    assert_eq!(buffered.len(), 2);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn test_committed_transaction_is_pruned_and_checkpointed() {
+    let dir = test_dir("committed");
+    let decoder = Decoder::new();
+
+    let target = DurableOutput::open(&dir).unwrap();
+    target.write_change(&begin("0/10", 1), &decoder).await.unwrap();
+    target.write_change(&insert("1"), &decoder).await.unwrap();
+    target.write_change(&commit("0/20"), &decoder).await.unwrap();
+
+    let buffered: Vec<Change> = target.unacknowledged().collect::<Result<_, _>>().unwrap();
+    assert_eq!(buffered.len(), 0, "a committed transaction's changes should be pruned");
+    assert_eq!(target.resume_from().unwrap(), Some("0/20".parse().unwrap()));
+
+    drop(target);
+    let reopened = DurableOutput::open(&dir).unwrap();
+    assert_eq!(reopened.resume_from().unwrap(), Some("0/20".parse().unwrap()));
+    assert_eq!(reopened.unacknowledged().count(), 0);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn test_checkpoint_advances_across_multiple_transactions() {
+    let dir = test_dir("checkpoint_advances");
+    let decoder = Decoder::new();
+
+    let target = DurableOutput::open(&dir).unwrap();
+    target.write_change(&begin("0/10", 1), &decoder).await.unwrap();
+    target.write_change(&insert("1"), &decoder).await.unwrap();
+    target.write_change(&commit("0/20"), &decoder).await.unwrap();
+
+    target.write_change(&begin("0/30", 2), &decoder).await.unwrap();
+    target.write_change(&insert("2"), &decoder).await.unwrap();
+    // Second transaction not yet committed.
+
+    assert_eq!(target.resume_from().unwrap(), Some("0/20".parse().unwrap()));
+    let buffered: Vec<Change> = target.unacknowledged().collect::<Result<_, _>>().unwrap();
+    assert_eq!(buffered.len(), 2, "only the uncommitted second transaction should remain buffered");
+
+    target.write_change(&commit("0/40"), &decoder).await.unwrap();
+    assert_eq!(target.resume_from().unwrap(), Some("0/40".parse().unwrap()));
+    assert_eq!(target.unacknowledged().count(), 0);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+/// Streamed (in-progress) transactions open with `StreamStart`, not `Begin`,
+/// and their rows don't carry an LSN until the matching `StreamCommit` - so
+/// they need their own path to get buffered, checkpointed, and pruned.
+#[tokio::test]
+async fn test_streamed_transaction_is_buffered_pruned_and_checkpointed() {
+    let dir = test_dir("streamed");
+    let decoder = Decoder::new();
+
+    let target = DurableOutput::open(&dir).unwrap();
+    target.write_change(&stream_start(7), &decoder).await.unwrap();
+    target.write_change(&stream_insert(7, "1"), &decoder).await.unwrap();
+    target.write_change(&stream_insert(7, "2"), &decoder).await.unwrap();
+
+    // Not yet committed: the rows (keyed by their xid, since there's no LSN
+    // yet) should still be sitting in the buffer.
+    assert_eq!(target.resume_from().unwrap(), None);
+    let buffered: Vec<Change> = target.unacknowledged().collect::<Result<_, _>>().unwrap();
+    assert_eq!(buffered.len(), 3, "StreamStart plus both StreamInsert rows should be buffered");
+
+    target.write_change(&stream_commit(7, "0/50"), &decoder).await.unwrap();
+
+    assert_eq!(target.resume_from().unwrap(), Some("0/50".parse().unwrap()));
+    assert_eq!(target.unacknowledged().count(), 0, "a committed streamed transaction's rows should be pruned");
+
+    drop(target);
+    let reopened = DurableOutput::open(&dir).unwrap();
+    assert_eq!(reopened.resume_from().unwrap(), Some("0/50".parse().unwrap()));
+    assert_eq!(reopened.unacknowledged().count(), 0);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -1,9 +1,10 @@
 use pgoutput_cmdline::decoder::*;
-use std::collections::HashMap;
+use pgoutput_cmdline::subject::SubjectBuilder;
+use indexmap::IndexMap;
 
 // Helper function to create test changes
 fn create_insert_change(schema: &str, table: &str) -> Change {
-    let mut new_tuple = HashMap::new();
+    let mut new_tuple = IndexMap::new();
     new_tuple.insert("id".to_string(), Some("1".to_string()));
     new_tuple.insert("name".to_string(), Some("Test".to_string()));
     
@@ -16,10 +17,10 @@ fn create_insert_change(schema: &str, table: &str) -> Change {
 }
 
 fn create_update_change(schema: &str, table: &str) -> Change {
-    let mut old_tuple = HashMap::new();
+    let mut old_tuple = IndexMap::new();
     old_tuple.insert("id".to_string(), Some("1".to_string()));
     
-    let mut new_tuple = HashMap::new();
+    let mut new_tuple = IndexMap::new();
     new_tuple.insert("id".to_string(), Some("1".to_string()));
     new_tuple.insert("name".to_string(), Some("Updated".to_string()));
     
@@ -33,7 +34,7 @@ fn create_update_change(schema: &str, table: &str) -> Change {
 }
 
 fn create_delete_change(schema: &str, table: &str) -> Change {
-    let mut old_tuple = HashMap::new();
+    let mut old_tuple = IndexMap::new();
     old_tuple.insert("id".to_string(), Some("1".to_string()));
     
     Change::Delete {
@@ -263,7 +264,7 @@ fn test_change_serialization_roundtrip() {
 #[test]
 fn test_nats_payload_size_reasonable() {
     // Verify that serialized payloads are reasonable sizes
-    let mut large_tuple = HashMap::new();
+    let mut large_tuple = IndexMap::new();
     for i in 0..100 {
         large_tuple.insert(
             format!("column_{}", i),
@@ -348,7 +349,7 @@ fn test_empty_tuple_serialization() {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
-        new_tuple: HashMap::new(),
+        new_tuple: IndexMap::new(),
     };
     
     // Should serialize even with empty tuple
@@ -384,7 +385,7 @@ fn test_large_lsn_values() {
 /// Verifies that international characters (Chinese, Norwegian, German) are preserved through serialization.
 #[test]
 fn test_unicode_in_table_data() {
-    let mut tuple = HashMap::new();
+    let mut tuple = IndexMap::new();
     tuple.insert("name".to_string(), Some("测试用户".to_string()));
     tuple.insert("description".to_string(), Some("Tëst Üsér".to_string()));
     
@@ -407,3 +408,32 @@ fn test_unicode_in_table_data() {
         _ => panic!("Expected Insert variant"),
     }
 }
+
+/// Tests that a multi-table `TRUNCATE a, b, c` routes under one subject per
+/// table, instead of `subject()` alone collapsing it onto just the first
+/// table's subject and silently losing routing visibility for the rest.
+#[test]
+fn test_nats_subjects_for_multi_table_truncate() {
+    let change = Change::Truncate {
+        relations: vec![
+            ("public".to_string(), "users".to_string()),
+            ("public".to_string(), "orders".to_string()),
+            ("analytics".to_string(), "events".to_string()),
+        ],
+        cascade: false,
+        restart_identity: false,
+    };
+
+    let builder = SubjectBuilder::new("postgres");
+    let subjects = change.subjects(&builder);
+
+    assert_eq!(
+        subjects,
+        vec!["postgres.public.users.truncate", "postgres.public.orders.truncate", "postgres.analytics.events.truncate"]
+    );
+
+    // A single-relation Truncate still gets exactly one subject, same as
+    // `subject()` alone would give it.
+    let single = Change::Truncate { relations: vec![("public".to_string(), "users".to_string())], cascade: false, restart_identity: false };
+    assert_eq!(single.subjects(&builder), vec!["postgres.public.users.truncate"]);
+}
@@ -1,6 +1,6 @@
 use pgoutput_cmdline::output::*;
 use pgoutput_cmdline::decoder::*;
-use std::collections::HashMap;
+use indexmap::IndexMap;
 
 /// Tests parsing of 'json' output format string.
 /// Verifies that OutputFormat::from_str correctly recognizes and returns the Json variant.
@@ -11,11 +11,12 @@ fn test_output_format_from_str_json() {
 }
 
 /// Tests parsing of 'json-pretty' output format string.
-/// Verifies that the JsonPretty format is correctly recognized.
+/// Verifies that the JsonPretty format is correctly recognized, defaulting
+/// to a 2-space indent.
 #[test]
 fn test_output_format_from_str_json_pretty() {
     let format = OutputFormat::from_str("json-pretty").unwrap();
-    assert!(matches!(format, OutputFormat::JsonPretty));
+    assert!(matches!(format, OutputFormat::JsonPretty(2)));
 }
 
 /// Tests parsing of 'text' output format string for human-readable output.
@@ -83,7 +84,7 @@ fn test_json_serialization_commit() {
 /// Verifies that relation ID, schema, table name, and tuple data are correctly represented in JSON.
 #[test]
 fn test_json_serialization_insert() {
-    let mut new_tuple = HashMap::new();
+    let mut new_tuple = IndexMap::new();
     new_tuple.insert("id".to_string(), Some("1".to_string()));
     new_tuple.insert("name".to_string(), Some("Alice".to_string()));
     
@@ -108,7 +109,7 @@ fn test_json_serialization_insert() {
 /// Verifies that SQL NULL is properly represented as JSON null.
 #[test]
 fn test_json_serialization_insert_with_null() {
-    let mut new_tuple = HashMap::new();
+    let mut new_tuple = IndexMap::new();
     new_tuple.insert("id".to_string(), Some("1".to_string()));
     new_tuple.insert("email".to_string(), None);
     
@@ -132,10 +133,10 @@ fn test_json_serialization_insert_with_null() {
 /// Verifies that both old and new values are included when REPLICA IDENTITY FULL is used.
 #[test]
 fn test_json_serialization_update_with_old_tuple() {
-    let mut old_tuple = HashMap::new();
+    let mut old_tuple = IndexMap::new();
     old_tuple.insert("name".to_string(), Some("Bob".to_string()));
     
-    let mut new_tuple = HashMap::new();
+    let mut new_tuple = IndexMap::new();
     new_tuple.insert("name".to_string(), Some("Robert".to_string()));
     
     let change = Change::Update {
@@ -156,7 +157,7 @@ fn test_json_serialization_update_with_old_tuple() {
 /// Verifies proper handling when only new values are available (REPLICA IDENTITY DEFAULT).
 #[test]
 fn test_json_serialization_update_without_old_tuple() {
-    let mut new_tuple = HashMap::new();
+    let mut new_tuple = IndexMap::new();
     new_tuple.insert("name".to_string(), Some("Carol".to_string()));
     
     let change = Change::Update {
@@ -180,7 +181,7 @@ fn test_json_serialization_update_without_old_tuple() {
 /// Verifies that deleted row data (old tuple) is correctly serialized to JSON.
 #[test]
 fn test_json_serialization_delete() {
-    let mut old_tuple = HashMap::new();
+    let mut old_tuple = IndexMap::new();
     old_tuple.insert("id".to_string(), Some("42".to_string()));
     
     let change = Change::Delete {
@@ -249,7 +250,7 @@ fn test_json_pretty_format() {
 /// Verifies that quotes, backslashes, and other special characters are properly escaped.
 #[test]
 fn test_json_special_characters() {
-    let mut new_tuple = HashMap::new();
+    let mut new_tuple = IndexMap::new();
     new_tuple.insert("description".to_string(), Some("Test \"quotes\" and \\backslash".to_string()));
     
     let change = Change::Insert {
@@ -273,7 +274,7 @@ fn test_json_special_characters() {
 /// Verifies that international characters (Norwegian, German, Chinese) are preserved correctly in JSON.
 #[test]
 fn test_json_unicode() {
-    let mut new_tuple = HashMap::new();
+    let mut new_tuple = IndexMap::new();
     new_tuple.insert("name".to_string(), Some("Håkon Müller 李明".to_string()));
     
     let change = Change::Insert {
@@ -295,7 +296,7 @@ fn test_json_unicode() {
 /// Verifies that empty string values are correctly represented as "" in JSON output.
 #[test]
 fn test_json_empty_string() {
-    let mut new_tuple = HashMap::new();
+    let mut new_tuple = IndexMap::new();
     new_tuple.insert("description".to_string(), Some("".to_string()));
     
     let change = Change::Insert {
@@ -319,9 +320,10 @@ fn test_json_empty_string() {
 /// Verifies that the OutputTarget trait correctly handles INSERT events without panicking.
 #[tokio::test]
 async fn test_stdout_output_insert() {
+    let decoder = Decoder::new();
     let output = StdoutOutput::new(OutputFormat::Json);
     
-    let mut new_tuple = HashMap::new();
+    let mut new_tuple = IndexMap::new();
     new_tuple.insert("id".to_string(), Some("1".to_string()));
     new_tuple.insert("name".to_string(), Some("Alice".to_string()));
     
@@ -333,20 +335,21 @@ async fn test_stdout_output_insert() {
     };
     
     // Should not panic
-    output.write_change(&change).await.unwrap();
+    output.write_change(&change, &decoder).await.unwrap();
 }
 
 /// Tests async StdoutOutput implementation for UPDATE operations.
 /// Verifies correct handling of UPDATE events with both old and new tuple data.
 #[tokio::test]
 async fn test_stdout_output_update() {
+    let decoder = Decoder::new();
     let output = StdoutOutput::new(OutputFormat::Json);
     
-    let mut old_tuple = HashMap::new();
+    let mut old_tuple = IndexMap::new();
     old_tuple.insert("id".to_string(), Some("1".to_string()));
     old_tuple.insert("name".to_string(), Some("Alice".to_string()));
     
-    let mut new_tuple = HashMap::new();
+    let mut new_tuple = IndexMap::new();
     new_tuple.insert("id".to_string(), Some("1".to_string()));
     new_tuple.insert("name".to_string(), Some("Alice Updated".to_string()));
     
@@ -358,16 +361,17 @@ async fn test_stdout_output_update() {
         new_tuple,
     };
     
-    output.write_change(&change).await.unwrap();
+    output.write_change(&change, &decoder).await.unwrap();
 }
 
 /// Tests async StdoutOutput implementation for DELETE operations.
 /// Verifies that DELETE events are properly output using JSON-pretty format.
 #[tokio::test]
 async fn test_stdout_output_delete() {
-    let output = StdoutOutput::new(OutputFormat::JsonPretty);
+    let decoder = Decoder::new();
+    let output = StdoutOutput::new(OutputFormat::JsonPretty(2));
     
-    let mut old_tuple = HashMap::new();
+    let mut old_tuple = IndexMap::new();
     old_tuple.insert("id".to_string(), Some("1".to_string()));
     old_tuple.insert("name".to_string(), Some("Alice".to_string()));
     
@@ -378,13 +382,14 @@ async fn test_stdout_output_delete() {
         old_tuple,
     };
     
-    output.write_change(&change).await.unwrap();
+    output.write_change(&change, &decoder).await.unwrap();
 }
 
 /// Tests async StdoutOutput for transaction boundary events.
 /// Verifies that BEGIN and COMMIT events are correctly output in text format.
 #[tokio::test]
 async fn test_stdout_output_transaction_events() {
+    let decoder = Decoder::new();
     let output = StdoutOutput::new(OutputFormat::Text);
     
     let begin = Change::Begin {
@@ -392,19 +397,20 @@ async fn test_stdout_output_transaction_events() {
         timestamp: 730826470123456,
         xid: 1000,
     };
-    output.write_change(&begin).await.unwrap();
+    output.write_change(&begin, &decoder).await.unwrap();
     
     let commit = Change::Commit {
         lsn: "0/16B2E20".to_string(),
         timestamp: 730826470123457,
     };
-    output.write_change(&commit).await.unwrap();
+    output.write_change(&commit, &decoder).await.unwrap();
 }
 
 /// Tests async StdoutOutput for RELATION metadata events.
 /// Verifies that table schema definitions are properly output including column information.
 #[tokio::test]
 async fn test_stdout_output_relation() {
+    let decoder = Decoder::new();
     let output = StdoutOutput::new(OutputFormat::Json);
     
     let columns = vec![
@@ -427,19 +433,20 @@ async fn test_stdout_output_relation() {
         columns,
     };
     
-    output.write_change(&change).await.unwrap();
+    output.write_change(&change, &decoder).await.unwrap();
 }
 
 /// Tests CompositeOutput with a single output target.
 /// Verifies that the multiplexer correctly forwards events to a single StdoutOutput.
 #[tokio::test]
 async fn test_composite_output_with_single_target() {
+    let decoder = Decoder::new();
     use std::sync::Arc;
     
     let stdout = StdoutOutput::new(OutputFormat::Json);
     let composite = CompositeOutput::new(vec![Arc::new(stdout)]);
     
-    let mut new_tuple = HashMap::new();
+    let mut new_tuple = IndexMap::new();
     new_tuple.insert("id".to_string(), Some("1".to_string()));
     
     let change = Change::Insert {
@@ -449,13 +456,14 @@ async fn test_composite_output_with_single_target() {
         new_tuple,
     };
     
-    composite.write_change(&change).await.unwrap();
+    composite.write_change(&change, &decoder).await.unwrap();
 }
 
 /// Tests CompositeOutput with multiple output targets.
 /// Verifies that events are correctly sent to multiple outputs (JSON and Text formats).
 #[tokio::test]
 async fn test_composite_output_with_multiple_targets() {
+    let decoder = Decoder::new();
     use std::sync::Arc;
     
     let stdout1 = StdoutOutput::new(OutputFormat::Json);
@@ -465,7 +473,7 @@ async fn test_composite_output_with_multiple_targets() {
         Arc::new(stdout2),
     ]);
     
-    let mut new_tuple = HashMap::new();
+    let mut new_tuple = IndexMap::new();
     new_tuple.insert("id".to_string(), Some("1".to_string()));
     
     let change = Change::Insert {
@@ -475,16 +483,17 @@ async fn test_composite_output_with_multiple_targets() {
         new_tuple,
     };
     
-    composite.write_change(&change).await.unwrap();
+    composite.write_change(&change, &decoder).await.unwrap();
 }
 
 /// Tests CompositeOutput with no output targets.
 /// Verifies that the multiplexer handles empty target lists gracefully without panicking.
 #[tokio::test]
 async fn test_composite_output_empty_targets() {
+    let decoder = Decoder::new();
     let composite = CompositeOutput::new(vec![]);
     
-    let mut new_tuple = HashMap::new();
+    let mut new_tuple = IndexMap::new();
     new_tuple.insert("id".to_string(), Some("1".to_string()));
     
     let change = Change::Insert {
@@ -495,13 +504,14 @@ async fn test_composite_output_empty_targets() {
     };
     
     // Should not panic with no targets
-    composite.write_change(&change).await.unwrap();
+    composite.write_change(&change, &decoder).await.unwrap();
 }
 
 /// Tests complete transaction flow through OutputTarget.
 /// Verifies proper handling of Begin, Relation, INSERT, UPDATE, DELETE, and Commit in sequence.
 #[tokio::test]
 async fn test_full_transaction_flow_through_output() {
+    let decoder = Decoder::new();
     let output = StdoutOutput::new(OutputFormat::Json);
     
     // Begin transaction
@@ -510,7 +520,7 @@ async fn test_full_transaction_flow_through_output() {
         timestamp: 1234567890,
         xid: 500,
     };
-    output.write_change(&begin).await.unwrap();
+    output.write_change(&begin, &decoder).await.unwrap();
     
     // Relation metadata
     let columns = vec![
@@ -526,10 +536,10 @@ async fn test_full_transaction_flow_through_output() {
         table: "test_table".to_string(),
         columns,
     };
-    output.write_change(&relation).await.unwrap();
+    output.write_change(&relation, &decoder).await.unwrap();
     
     // Insert
-    let mut insert_tuple = HashMap::new();
+    let mut insert_tuple = IndexMap::new();
     insert_tuple.insert("id".to_string(), Some("1".to_string()));
     let insert = Change::Insert {
         relation_id: 16384,
@@ -537,12 +547,12 @@ async fn test_full_transaction_flow_through_output() {
         table: "test_table".to_string(),
         new_tuple: insert_tuple,
     };
-    output.write_change(&insert).await.unwrap();
+    output.write_change(&insert, &decoder).await.unwrap();
     
     // Update
-    let mut old_tuple = HashMap::new();
+    let mut old_tuple = IndexMap::new();
     old_tuple.insert("id".to_string(), Some("1".to_string()));
-    let mut new_tuple = HashMap::new();
+    let mut new_tuple = IndexMap::new();
     new_tuple.insert("id".to_string(), Some("2".to_string()));
     let update = Change::Update {
         relation_id: 16384,
@@ -551,10 +561,10 @@ async fn test_full_transaction_flow_through_output() {
         old_tuple: Some(old_tuple),
         new_tuple,
     };
-    output.write_change(&update).await.unwrap();
+    output.write_change(&update, &decoder).await.unwrap();
     
     // Delete
-    let mut delete_tuple = HashMap::new();
+    let mut delete_tuple = IndexMap::new();
     delete_tuple.insert("id".to_string(), Some("2".to_string()));
     let delete = Change::Delete {
         relation_id: 16384,
@@ -562,23 +572,24 @@ async fn test_full_transaction_flow_through_output() {
         table: "test_table".to_string(),
         old_tuple: delete_tuple,
     };
-    output.write_change(&delete).await.unwrap();
+    output.write_change(&delete, &decoder).await.unwrap();
     
     // Commit transaction
     let commit = Change::Commit {
         lsn: "0/200".to_string(),
         timestamp: 1234567900,
     };
-    output.write_change(&commit).await.unwrap();
+    output.write_change(&commit, &decoder).await.unwrap();
 }
 
 /// Tests StdoutOutput handling of NULL values in tuple data.
 /// Verifies that NULL columns are correctly represented in the output.
 #[tokio::test]
 async fn test_stdout_output_with_null_values() {
+    let decoder = Decoder::new();
     let output = StdoutOutput::new(OutputFormat::Json);
     
-    let mut new_tuple = HashMap::new();
+    let mut new_tuple = IndexMap::new();
     new_tuple.insert("id".to_string(), Some("1".to_string()));
     new_tuple.insert("email".to_string(), None);
     new_tuple.insert("name".to_string(), Some("Alice".to_string()));
@@ -590,16 +601,17 @@ async fn test_stdout_output_with_null_values() {
         new_tuple,
     };
     
-    output.write_change(&change).await.unwrap();
+    output.write_change(&change, &decoder).await.unwrap();
 }
 
 /// Tests StdoutOutput with non-standard schema names.
 /// Verifies handling of schema names containing hyphens and underscores.
 #[tokio::test]
 async fn test_stdout_output_with_special_schema_names() {
+    let decoder = Decoder::new();
     let output = StdoutOutput::new(OutputFormat::Json);
     
-    let mut new_tuple = HashMap::new();
+    let mut new_tuple = IndexMap::new();
     new_tuple.insert("id".to_string(), Some("1".to_string()));
     
     let change = Change::Insert {
@@ -609,16 +621,17 @@ async fn test_stdout_output_with_special_schema_names() {
         new_tuple,
     };
     
-    output.write_change(&change).await.unwrap();
+    output.write_change(&change, &decoder).await.unwrap();
 }
 
 /// Tests text format output for human readability.
 /// Verifies that text format correctly displays INSERT operations in readable form.
 #[tokio::test]
 async fn test_stdout_output_text_format() {
+    let decoder = Decoder::new();
     let output = StdoutOutput::new(OutputFormat::Text);
     
-    let mut new_tuple = HashMap::new();
+    let mut new_tuple = IndexMap::new();
     new_tuple.insert("id".to_string(), Some("1".to_string()));
     new_tuple.insert("name".to_string(), Some("Test User".to_string()));
     
@@ -630,24 +643,25 @@ async fn test_stdout_output_text_format() {
     };
     
     // Text format should not panic
-    output.write_change(&change).await.unwrap();
+    output.write_change(&change, &decoder).await.unwrap();
 }
 
 /// Tests multiple INSERT operations through CompositeOutput.
 /// Verifies that sequential inserts to different tables are handled correctly by multiple outputs.
 #[tokio::test]
 async fn test_multiple_inserts_through_composite() {
+    let decoder = Decoder::new();
     use std::sync::Arc;
     
     let output1 = StdoutOutput::new(OutputFormat::Json);
-    let output2 = StdoutOutput::new(OutputFormat::JsonPretty);
+    let output2 = StdoutOutput::new(OutputFormat::JsonPretty(2));
     let composite = CompositeOutput::new(vec![
         Arc::new(output1),
         Arc::new(output2),
     ]);
     
     // First insert
-    let mut tuple1 = HashMap::new();
+    let mut tuple1 = IndexMap::new();
     tuple1.insert("id".to_string(), Some("1".to_string()));
     let change1 = Change::Insert {
         relation_id: 16384,
@@ -655,10 +669,10 @@ async fn test_multiple_inserts_through_composite() {
         table: "users".to_string(),
         new_tuple: tuple1,
     };
-    composite.write_change(&change1).await.unwrap();
+    composite.write_change(&change1, &decoder).await.unwrap();
     
     // Second insert
-    let mut tuple2 = HashMap::new();
+    let mut tuple2 = IndexMap::new();
     tuple2.insert("id".to_string(), Some("2".to_string()));
     let change2 = Change::Insert {
         relation_id: 16384,
@@ -666,5 +680,52 @@ async fn test_multiple_inserts_through_composite() {
         table: "orders".to_string(),
         new_tuple: tuple2,
     };
-    composite.write_change(&change2).await.unwrap();
+    composite.write_change(&change2, &decoder).await.unwrap();
+}
+
+/// Tests parsing of 'json-pretty:<width>' output format strings.
+/// Verifies the indent width is parsed out of the suffix, and that an
+/// invalid width is rejected.
+#[test]
+fn test_output_format_from_str_json_pretty_with_width() {
+    assert!(matches!(OutputFormat::from_str("json-pretty:4").unwrap(), OutputFormat::JsonPretty(4)));
+    assert!(matches!(OutputFormat::from_str("json-pretty:0").unwrap(), OutputFormat::JsonPretty(0)));
+    assert!(OutputFormat::from_str("json-pretty:nope").is_err());
+}
+
+/// Tests parsing of the 'ndjson' and 'toml' output format strings.
+#[test]
+fn test_output_format_from_str_ndjson_and_toml() {
+    assert!(matches!(OutputFormat::from_str("ndjson").unwrap(), OutputFormat::Ndjson));
+    assert!(matches!(OutputFormat::from_str("toml").unwrap(), OutputFormat::Toml));
+}
+
+/// `json-pretty:0` should still print one field per line (via the pretty
+/// formatter's newlines) but with no leading whitespace at all.
+#[test]
+fn test_pretty_json_zero_indent_has_no_indentation() {
+    let value = serde_json::json!({"a": 1, "b": {"c": 2}});
+
+    let unindented = to_pretty_json(&value, 0).unwrap();
+    assert!(!unindented.lines().any(|line| line.starts_with(' ')));
+    assert!(unindented.contains('\n'), "should still be one field per line");
+
+    let indented = to_pretty_json(&value, 4).unwrap();
+    assert!(indented.lines().any(|line| line.starts_with("    ")));
+}
+
+/// TOML round-trips a `Change::Insert` built from non-NULL columns (TOML
+/// has no `null`, so NULL-carrying tuples are out of scope for this format).
+#[test]
+fn test_toml_round_trips_insert() {
+    let mut new_tuple = IndexMap::new();
+    new_tuple.insert("id".to_string(), Some("1".to_string()));
+    new_tuple.insert("name".to_string(), Some("Alice".to_string()));
+
+    let change = Change::Insert { relation_id: 16384, schema: "public".to_string(), table: "users".to_string(), new_tuple };
+
+    let toml_text = to_toml(&change).unwrap();
+    let round_tripped: Change = toml::from_str(&toml_text).unwrap();
+
+    assert_eq!(serde_json::to_value(&round_tripped).unwrap(), serde_json::to_value(&change).unwrap());
 }